@@ -7,24 +7,16 @@ use zed_extension_api::{
     serde_json::Value,
 };
 
-use crate::language_servers::{Dexter, ElixirLs, Expert, Lexical, NextLs};
+use crate::language_servers::{CompletionItem, ElixirLs, LanguageServers};
 
 struct ElixirExtension {
-    expert: Option<Expert>,
-    elixir_ls: Option<ElixirLs>,
-    dexter: Option<Dexter>,
-    next_ls: Option<NextLs>,
-    lexical: Option<Lexical>,
+    language_servers: LanguageServers,
 }
 
 impl zed::Extension for ElixirExtension {
     fn new() -> Self {
         Self {
-            expert: None,
-            elixir_ls: None,
-            dexter: None,
-            next_ls: None,
-            lexical: None,
+            language_servers: LanguageServers::default(),
         }
     }
 
@@ -33,29 +25,13 @@ impl zed::Extension for ElixirExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<zed::Command> {
-        match language_server_id.as_ref() {
-            Expert::LANGUAGE_SERVER_ID => self
-                .expert
-                .get_or_insert_with(Expert::new)
-                .language_server_command(language_server_id, worktree),
-            ElixirLs::LANGUAGE_SERVER_ID => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
-                .language_server_command(language_server_id, worktree),
-            Dexter::LANGUAGE_SERVER_ID => self
-                .dexter
-                .get_or_insert_with(Dexter::new)
-                .language_server_command(language_server_id, worktree),
-            NextLs::LANGUAGE_SERVER_ID => self
-                .next_ls
-                .get_or_insert_with(NextLs::new)
-                .language_server_command(language_server_id, worktree),
-            Lexical::LANGUAGE_SERVER_ID => self
-                .lexical
-                .get_or_insert_with(Lexical::new)
-                .language_server_command(language_server_id, worktree),
-            language_server_id => Err(format!("unknown language server: {language_server_id}")),
-        }
+        self.language_servers
+            .forget_reported_settings(language_server_id.as_ref());
+
+        self.language_servers
+            .get_or_init(language_server_id.as_ref())
+            .ok_or_else(|| format!("unknown language server: {language_server_id}"))?
+            .language_server_command(language_server_id, worktree)
     }
 
     fn language_server_initialization_options(
@@ -63,28 +39,17 @@ impl zed::Extension for ElixirExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<Option<Value>> {
-        match language_server_id.as_ref() {
-            Expert::LANGUAGE_SERVER_ID => self
-                .expert
-                .get_or_insert_with(Expert::new)
-                .language_server_initialization_options(worktree),
-            ElixirLs::LANGUAGE_SERVER_ID => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
-                .language_server_initialization_options(worktree),
-            Dexter::LANGUAGE_SERVER_ID => self
-                .dexter
-                .get_or_insert_with(Dexter::new)
-                .language_server_initialization_options(worktree),
-            NextLs::LANGUAGE_SERVER_ID => self
-                .next_ls
-                .get_or_insert_with(NextLs::new)
-                .language_server_initialization_options(worktree),
-            Lexical::LANGUAGE_SERVER_ID => self
-                .lexical
-                .get_or_insert_with(Lexical::new)
-                .language_server_initialization_options(worktree),
-            _ => Ok(None),
+        self.language_servers
+            .report_invalid_settings(language_server_id, worktree);
+
+        match self
+            .language_servers
+            .get_or_init(language_server_id.as_ref())
+        {
+            Some(language_server) => {
+                language_server.language_server_initialization_options(worktree)
+            }
+            None => Ok(None),
         }
     }
 
@@ -93,28 +58,17 @@ impl zed::Extension for ElixirExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<Option<Value>> {
-        match language_server_id.as_ref() {
-            Expert::LANGUAGE_SERVER_ID => self
-                .expert
-                .get_or_insert_with(Expert::new)
-                .language_server_workspace_configuration(worktree),
-            ElixirLs::LANGUAGE_SERVER_ID => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
-                .language_server_workspace_configuration(worktree),
-            Dexter::LANGUAGE_SERVER_ID => self
-                .dexter
-                .get_or_insert_with(Dexter::new)
-                .language_server_workspace_configuration(worktree),
-            NextLs::LANGUAGE_SERVER_ID => self
-                .next_ls
-                .get_or_insert_with(NextLs::new)
-                .language_server_workspace_configuration(worktree),
-            Lexical::LANGUAGE_SERVER_ID => self
-                .lexical
-                .get_or_insert_with(Lexical::new)
-                .language_server_workspace_configuration(worktree),
-            _ => Ok(None),
+        self.language_servers
+            .report_invalid_settings(language_server_id, worktree);
+
+        match self
+            .language_servers
+            .get_or_init(language_server_id.as_ref())
+        {
+            Some(language_server) => {
+                language_server.language_server_workspace_configuration(worktree)
+            }
+            None => Ok(None),
         }
     }
 
@@ -123,16 +77,9 @@ impl zed::Extension for ElixirExtension {
         language_server_id: &LanguageServerId,
         completion: Completion,
     ) -> Option<CodeLabel> {
-        match language_server_id.as_ref() {
-            Expert::LANGUAGE_SERVER_ID => self.expert.as_ref()?.label_for_completion(completion),
-            ElixirLs::LANGUAGE_SERVER_ID => {
-                self.elixir_ls.as_ref()?.label_for_completion(completion)
-            }
-            Dexter::LANGUAGE_SERVER_ID => self.dexter.as_ref()?.label_for_completion(completion),
-            NextLs::LANGUAGE_SERVER_ID => self.next_ls.as_ref()?.label_for_completion(completion),
-            Lexical::LANGUAGE_SERVER_ID => self.lexical.as_ref()?.label_for_completion(completion),
-            _ => None,
-        }
+        self.language_servers
+            .get(language_server_id.as_ref())?
+            .label_for_completion(&CompletionItem::from(&completion))
     }
 
     fn label_for_symbol(
@@ -140,14 +87,9 @@ impl zed::Extension for ElixirExtension {
         language_server_id: &LanguageServerId,
         symbol: Symbol,
     ) -> Option<CodeLabel> {
-        match language_server_id.as_ref() {
-            Expert::LANGUAGE_SERVER_ID => self.expert.as_ref()?.label_for_symbol(symbol),
-            ElixirLs::LANGUAGE_SERVER_ID => self.elixir_ls.as_ref()?.label_for_symbol(symbol),
-            Dexter::LANGUAGE_SERVER_ID => self.dexter.as_ref()?.label_for_symbol(symbol),
-            NextLs::LANGUAGE_SERVER_ID => self.next_ls.as_ref()?.label_for_symbol(symbol),
-            Lexical::LANGUAGE_SERVER_ID => self.lexical.as_ref()?.label_for_symbol(symbol),
-            _ => None,
-        }
+        self.language_servers
+            .get(language_server_id.as_ref())?
+            .label_for_symbol(symbol)
     }
 
     fn get_dap_binary(
@@ -158,10 +100,11 @@ impl zed::Extension for ElixirExtension {
         worktree: &Worktree,
     ) -> Result<DebugAdapterBinary> {
         match adapter_name.as_str() {
-            ElixirLs::DEBUG_ADAPTER_NAME => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
-                .get_dap_binary(config, user_provided_debug_adapter_path, worktree),
+            ElixirLs::DEBUG_ADAPTER_NAME => self.language_servers.elixir_ls()?.get_dap_binary(
+                config,
+                user_provided_debug_adapter_path,
+                worktree,
+            ),
             adapter_name => Err(format!("unknown debug adapter: {adapter_name}")),
         }
     }
@@ -172,10 +115,9 @@ impl zed::Extension for ElixirExtension {
         config: Value,
     ) -> Result<StartDebuggingRequestArgumentsRequest> {
         match adapter_name.as_str() {
-            ElixirLs::DEBUG_ADAPTER_NAME => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
-                .dap_request_kind(config),
+            ElixirLs::DEBUG_ADAPTER_NAME => {
+                self.language_servers.elixir_ls()?.dap_request_kind(config)
+            }
             adapter_name => Err(format!("unknown debug adapter: {adapter_name}")),
         }
     }
@@ -183,8 +125,8 @@ impl zed::Extension for ElixirExtension {
     fn dap_config_to_scenario(&mut self, config: DebugConfig) -> Result<DebugScenario> {
         match config.adapter.as_str() {
             ElixirLs::DEBUG_ADAPTER_NAME => self
-                .elixir_ls
-                .get_or_insert_with(ElixirLs::new)
+                .language_servers
+                .elixir_ls()?
                 .dap_config_to_scenario(config),
             adapter_name => Err(format!("unknown debug adapter: {adapter_name}")),
         }
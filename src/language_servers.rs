@@ -1,13 +1,222 @@
+mod binary;
+mod checksum;
 mod config;
 mod dexter;
 mod elixir_ls;
 mod expert;
+mod installer;
+mod labels;
 mod lexical;
 mod next_ls;
+mod schema;
+mod toolchain;
 mod util;
+mod version;
+mod version_managers;
+
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+};
+
+use zed_extension_api::{
+    self as zed, CodeLabel, LanguageServerId, Result, Worktree, lsp::Symbol, serde_json::Value,
+};
 
 pub use dexter::*;
 pub use elixir_ls::*;
 pub use expert::*;
+pub use labels::CompletionItem;
 pub use lexical::*;
 pub use next_ls::*;
+
+/// The hooks every Elixir language server integration implements.
+pub trait LanguageServer: Any + Send + Sync {
+    fn language_server_command(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<zed::Command>;
+
+    fn language_server_initialization_options(
+        &mut self,
+        _worktree: &Worktree,
+    ) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn language_server_workspace_configuration(
+        &mut self,
+        _worktree: &Worktree,
+    ) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn label_for_completion(&self, completion: &CompletionItem) -> Option<CodeLabel> {
+        labels::completion(completion)
+    }
+
+    fn label_for_symbol(&self, symbol: Symbol) -> Option<CodeLabel> {
+        labels::symbol(&symbol)
+    }
+}
+
+type LanguageServerConstructor = fn() -> Box<dyn LanguageServer>;
+
+/// The language servers provided by this extension, keyed by their `LANGUAGE_SERVER_ID`.
+const LANGUAGE_SERVERS: &[(&str, LanguageServerConstructor)] = &[
+    (Expert::LANGUAGE_SERVER_ID, || Box::new(Expert::new())),
+    (ElixirLs::LANGUAGE_SERVER_ID, || Box::new(ElixirLs::new())),
+    (Dexter::LANGUAGE_SERVER_ID, || Box::new(Dexter::new())),
+    (NextLs::LANGUAGE_SERVER_ID, || Box::new(NextLs::new())),
+    (Lexical::LANGUAGE_SERVER_ID, || Box::new(Lexical::new())),
+];
+
+/// Lazily instantiates language servers on first use and keeps them around for later hooks.
+#[derive(Default)]
+pub struct LanguageServers {
+    instances: HashMap<&'static str, Box<dyn LanguageServer>>,
+    reported_settings_problems: HashMap<String, Vec<String>>,
+    /// The servers whose status currently shows their settings problems.
+    settings_statuses: HashSet<String>,
+}
+
+impl LanguageServers {
+    pub fn get(&self, language_server_id: &str) -> Option<&dyn LanguageServer> {
+        self.instances
+            .get(language_server_id)
+            .map(|language_server| language_server.as_ref())
+    }
+
+    pub fn get_or_init(&mut self, language_server_id: &str) -> Option<&mut dyn LanguageServer> {
+        let (id, new) = LANGUAGE_SERVERS
+            .iter()
+            .find(|(id, _)| *id == language_server_id)?;
+
+        Some(self.instances.entry(id).or_insert_with(new).as_mut())
+    }
+
+    /// Returns the ElixirLS instance, which also provides the ElixirLS debug adapter, so that the
+    /// language server and the debug adapter share the release they install.
+    pub fn elixir_ls(&mut self) -> Result<&mut ElixirLs> {
+        let language_server: &mut dyn Any = self
+            .get_or_init(ElixirLs::LANGUAGE_SERVER_ID)
+            .ok_or("ElixirLS is not a registered language server")?;
+
+        language_server
+            .downcast_mut()
+            .ok_or_else(|| "ElixirLS is registered with an unexpected type".to_string())
+    }
+
+    /// Reports settings that don't match the server's schema, as language servers silently ignore
+    /// them. Servers request their settings repeatedly, so problems are only reported when they
+    /// change.
+    ///
+    /// Only servers with a complete schema show problems in their status, others could be flagged
+    /// for settings the schema doesn't know about, so their problems are printed to Zed's stderr.
+    pub fn report_invalid_settings(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) {
+        let id = language_server_id.as_ref();
+        let problems = config::invalid_settings(id, worktree);
+        let reported = self
+            .reported_settings_problems
+            .entry(id.to_string())
+            .or_default();
+        if *reported == problems {
+            return;
+        }
+
+        if !schema::is_complete(id) {
+            for problem in &problems {
+                eprintln!("invalid `lsp.{id}` settings: {problem}");
+            }
+        } else if !problems.is_empty() {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Failed(format!(
+                    "invalid `lsp.{id}` settings: {}",
+                    problems.join("; ")
+                )),
+            );
+            self.settings_statuses.insert(id.to_string());
+        } else if self.settings_statuses.remove(id) {
+            // Only clear a status reporting settings problems, never one set while installing.
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::None,
+            );
+        }
+        *reported = problems;
+    }
+
+    /// Forgets the settings problems reported for a server that is restarting, as installing it
+    /// replaces its status.
+    pub fn forget_reported_settings(&mut self, language_server_id: &str) {
+        self.reported_settings_problems.remove(language_server_id);
+        self.settings_statuses.remove(language_server_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zed_extension_api::lsp::CompletionKind;
+
+    use super::*;
+
+    const KEYWORD: CompletionItem = CompletionItem {
+        label: "with",
+        kind: Some(CompletionKind::Keyword),
+        detail: None,
+        label_detail: None,
+        label_description: None,
+    };
+
+    #[test]
+    fn initializes_known_language_servers() {
+        let mut language_servers = LanguageServers::default();
+
+        for (language_server_id, _) in LANGUAGE_SERVERS {
+            assert!(language_servers.get(language_server_id).is_none());
+            assert!(language_servers.get_or_init(language_server_id).is_some());
+            assert!(language_servers.get(language_server_id).is_some());
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_language_servers() {
+        let mut language_servers = LanguageServers::default();
+
+        assert!(language_servers.get_or_init("credo-ls").is_none());
+        assert!(language_servers.get("credo-ls").is_none());
+        assert!(language_servers.instances.is_empty());
+    }
+
+    #[test]
+    fn debug_adapter_shares_the_elixir_ls_instance() {
+        let mut language_servers = LanguageServers::default();
+
+        let elixir_ls = language_servers.elixir_ls().unwrap() as *const ElixirLs;
+        let registered = language_servers
+            .get(ElixirLs::LANGUAGE_SERVER_ID)
+            .map(|language_server| language_server as *const dyn LanguageServer as *const ElixirLs);
+        assert_eq!(registered, Some(elixir_ls));
+        assert_eq!(language_servers.instances.len(), 1);
+    }
+
+    #[test]
+    fn labels_nothing_before_initialization() {
+        let mut language_servers = LanguageServers::default();
+        let label = |language_servers: &LanguageServers| {
+            language_servers
+                .get(Expert::LANGUAGE_SERVER_ID)
+                .and_then(|language_server| language_server.label_for_completion(&KEYWORD))
+        };
+
+        assert!(label(&language_servers).is_none());
+        language_servers.get_or_init(Expert::LANGUAGE_SERVER_ID);
+        assert!(label(&language_servers).is_some());
+    }
+}
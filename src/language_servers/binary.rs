@@ -0,0 +1,75 @@
+use std::fs;
+
+use zed_extension_api::{Result, Worktree};
+
+use crate::language_servers::{
+    config,
+    installer::{GithubReleaseInstaller, InstalledRelease},
+    version_managers,
+};
+
+/// A language server executable and the arguments it is started with.
+pub(super) struct LanguageServerBinary {
+    pub path: String,
+    pub args: Vec<String>,
+}
+
+/// How a language server's executable is found before a release is installed for it.
+pub(super) struct BinaryLookup<'a> {
+    /// The `lsp.<id>` settings key, also used as the asdf/mise tool name.
+    pub language_server_id: &'static str,
+    /// The name of the executable on the `PATH` and in version managers.
+    pub binary_name: &'a str,
+    /// The arguments used when `binary.arguments` is not configured.
+    pub default_args: &'a [&'a str],
+}
+
+impl BinaryLookup<'_> {
+    /// Finds the language server executable from, in order, the `binary.path` setting, the
+    /// worktree's `PATH`, a version manager declaring the server as a tool, and the release this
+    /// extension installed last. Only when all of them come up empty is `install` called with the
+    /// server's installer.
+    pub fn find_or_install(
+        &self,
+        worktree: &Worktree,
+        cached_binary_path: &mut Option<String>,
+        installer: impl FnOnce() -> Result<GithubReleaseInstaller>,
+        install: impl FnOnce(GithubReleaseInstaller) -> Result<InstalledRelease>,
+    ) -> Result<LanguageServerBinary> {
+        let binary_settings = config::get_binary_settings(self.language_server_id, worktree);
+        let args = config::get_binary_args(&binary_settings).unwrap_or_else(|| {
+            self.default_args
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect()
+        });
+
+        let path = config::get_binary_path(&binary_settings)
+            .or_else(|| worktree.which(self.binary_name))
+            .or_else(|| {
+                version_managers::which(worktree, self.language_server_id, self.binary_name)
+            });
+        if let Some(path) = path {
+            return Ok(LanguageServerBinary { path, args });
+        }
+
+        let installer = installer()?;
+
+        if let Some(path) = cached_binary_path
+            && installer.manages(path)
+            && fs::metadata(&path).is_ok_and(|stat| stat.is_file())
+        {
+            return Ok(LanguageServerBinary {
+                path: path.clone(),
+                args,
+            });
+        }
+
+        let installed = install(installer)?;
+        *cached_binary_path = Some(installed.binary_path.clone());
+        Ok(LanguageServerBinary {
+            path: installed.binary_path,
+            args,
+        })
+    }
+}
@@ -0,0 +1,170 @@
+use std::{fs::File, io::Write};
+
+use sha2::{Digest, Sha256};
+use zed_extension_api::{
+    GithubReleaseAsset, Result,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+};
+
+/// Returns the SHA-256 checksum a release publishes for the given asset, either in a dedicated
+/// `<asset>.sha256` file or in a combined `checksums.txt`/`SHA256SUMS` file.
+pub(super) fn published_checksum(
+    assets: &[GithubReleaseAsset],
+    asset_name: &str,
+) -> Result<Option<String>> {
+    let asset_checksum_names = [
+        format!("{asset_name}.sha256"),
+        format!("{asset_name}.sha256sum"),
+    ];
+    if let Some(checksum_asset) = assets
+        .iter()
+        .find(|asset| asset_checksum_names.contains(&asset.name))
+    {
+        let checksums = fetch_text(&checksum_asset.download_url)?;
+        return parse_checksums(&checksums, None)
+            .map(Some)
+            .ok_or_else(|| format!("no checksum found in {}", checksum_asset.name));
+    }
+
+    if let Some(checksum_asset) = assets.iter().find(|asset| {
+        asset.name == "SHA256SUMS"
+            || asset.name.ends_with("checksums.txt")
+            || asset.name.ends_with("checksums.sha256")
+    }) {
+        let checksums = fetch_text(&checksum_asset.download_url)?;
+        return parse_checksums(&checksums, Some(asset_name))
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "no checksum for {asset_name} found in {}",
+                    checksum_asset.name
+                )
+            });
+    }
+
+    Ok(None)
+}
+
+/// Streams the file at the given URL to `output_path`, returning its SHA-256 digest.
+pub(super) fn fetch_sha256(url: &str, output_path: &str) -> Result<String> {
+    let response = request(url)?
+        .fetch_stream()
+        .map_err(|e| format!("failed to download {url}: {e}"))?;
+    let mut output =
+        File::create(output_path).map_err(|e| format!("failed to create file: {e}"))?;
+
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response
+        .next_chunk()
+        .map_err(|e| format!("failed to download {url}: {e}"))?
+    {
+        hasher.update(&chunk);
+        output
+            .write_all(&chunk)
+            .map_err(|e| format!("failed to write file: {e}"))?;
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+pub(super) fn verify(asset_name: &str, expected: &str, actual: &str) -> Result<()> {
+    if expected.eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(format!(
+            "checksum mismatch for {asset_name}: expected sha256 {expected}, got {actual}"
+        ))
+    }
+}
+
+fn fetch_text(url: &str) -> Result<String> {
+    let response = request(url)?
+        .fetch()
+        .map_err(|e| format!("failed to download {url}: {e}"))?;
+
+    String::from_utf8(response.body).map_err(|e| format!("invalid checksum file {url}: {e}"))
+}
+
+fn request(url: &str) -> Result<HttpRequest> {
+    HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(url)
+        .redirect_policy(RedirectPolicy::FollowAll)
+        .build()
+}
+
+/// Parses `<sha256>  <file name>` lines, returning the checksum listed for `asset_name`, or the
+/// first checksum when no name is given.
+fn parse_checksums(checksums: &str, asset_name: Option<&str>) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let checksum = parts.next()?;
+        let file_name = parts.next().map(|name| name.trim_start_matches('*'));
+
+        let is_sha256 = checksum.len() == 64 && checksum.bytes().all(|b| b.is_ascii_hexdigit());
+        let matches_asset = asset_name.is_none_or(|asset_name| file_name == Some(asset_name));
+
+        (is_sha256 && matches_asset).then(|| checksum.to_ascii_lowercase())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn parse_checksums_finds_the_asset_entry() {
+        let checksums = format!("{EMPTY}  expert_linux_amd64\n{ABC}  expert_darwin_arm64\n");
+        assert_eq!(
+            parse_checksums(&checksums, Some("expert_darwin_arm64")).as_deref(),
+            Some(ABC)
+        );
+    }
+
+    #[test]
+    fn parse_checksums_accepts_binary_mode_names() {
+        let checksums = format!("{ABC} *next_ls_linux_amd64\n");
+        assert_eq!(
+            parse_checksums(&checksums, Some("next_ls_linux_amd64")).as_deref(),
+            Some(ABC)
+        );
+    }
+
+    #[test]
+    fn parse_checksums_lowercases_the_checksum() {
+        let checksums = format!("{}  lexical.zip\n", ABC.to_ascii_uppercase());
+        assert_eq!(
+            parse_checksums(&checksums, Some("lexical.zip")).as_deref(),
+            Some(ABC)
+        );
+    }
+
+    #[test]
+    fn parse_checksums_returns_none_for_missing_entry() {
+        let checksums = format!("{ABC}  expert_linux_amd64\n");
+        assert_eq!(
+            parse_checksums(&checksums, Some("expert_linux_arm64")),
+            None
+        );
+        assert_eq!(parse_checksums("not a checksum", None), None);
+    }
+
+    #[test]
+    fn parse_checksums_reads_single_hash_files() {
+        assert_eq!(
+            parse_checksums(&format!("{ABC}\n"), None).as_deref(),
+            Some(ABC)
+        );
+        assert_eq!(
+            parse_checksums(&format!("{ABC}  dexter\n"), None).as_deref(),
+            Some(ABC)
+        );
+    }
+}
@@ -1,22 +1,139 @@
+use std::str::FromStr;
+
 use zed_extension_api::{
-    Worktree,
+    EnvVars, Result, Worktree,
     serde_json::Value,
     settings::{CommandSettings, LspSettings},
 };
 
+use crate::language_servers::{installer::ReleaseChannel, schema};
+
+/// Returns the user's initialization options deep merged over the extension defaults.
 pub(super) fn get_initialization_options(
     language_server_id: &str,
     worktree: &Worktree,
-) -> Option<Value> {
-    LspSettings::for_worktree(language_server_id, worktree)
+    mut defaults: Value,
+) -> Value {
+    if let Some(initialization_options) = LspSettings::for_worktree(language_server_id, worktree)
         .ok()
         .and_then(|lsp_settings| lsp_settings.initialization_options)
+    {
+        merge(&mut defaults, initialization_options);
+    }
+    defaults
 }
 
+/// Recursively merges objects, with values from `overrides` winning per key. A `null` override
+/// removes the key, which allows opting out of a default, and is never forwarded itself.
+pub(super) fn merge(value: &mut Value, overrides: Value) {
+    match (value, overrides) {
+        (Value::Object(value), Value::Object(overrides)) => {
+            for (key, override_value) in overrides {
+                if override_value.is_null() {
+                    value.remove(&key);
+                } else if let Some(existing_value) = value.get_mut(&key) {
+                    merge(existing_value, override_value);
+                } else {
+                    value.insert(key, without_nulls(override_value));
+                }
+            }
+        }
+        (value, overrides) => *value = without_nulls(overrides),
+    }
+}
+
+/// Removes `null` object values, which have no default to remove when nothing is merged into.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Keys under `settings` that configure the extension itself and are not forwarded to the language server.
+const EXTENSION_SETTINGS: &[&str] = &["version", "channel", "keep_versions"];
+
 pub(super) fn get_workspace_configuration(
     language_server_id: &str,
     worktree: &Worktree,
 ) -> Option<Value> {
+    get_settings(language_server_id, worktree).map(|mut settings| {
+        if let Some(settings) = settings.as_object_mut() {
+            settings.retain(|key, _| !EXTENSION_SETTINGS.contains(&key.as_str()));
+        }
+        settings
+    })
+}
+
+pub(super) fn get_version(language_server_id: &str, worktree: &Worktree) -> Option<String> {
+    get_settings(language_server_id, worktree)
+        .and_then(|settings| settings.get("version")?.as_str().map(str::to_string))
+}
+
+/// Returns the configured release channel, which must be one of the channels the server publishes.
+pub(super) fn get_release_channel(
+    language_server_id: &str,
+    worktree: &Worktree,
+    supported_channels: &[ReleaseChannel],
+) -> Result<ReleaseChannel> {
+    let channel = get_settings(language_server_id, worktree)
+        .and_then(|settings| {
+            settings
+                .get("channel")?
+                .as_str()
+                .map(ReleaseChannel::from_str)
+        })
+        .unwrap_or(Ok(ReleaseChannel::Stable))?;
+
+    if !supported_channels.contains(&channel) {
+        let supported_channels = supported_channels
+            .iter()
+            .map(|channel| channel.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "{language_server_id} has no {} releases, use one of: {supported_channels}",
+            channel.name()
+        ));
+    }
+
+    Ok(channel)
+}
+
+/// Returns how many installed versions to keep, defaulting to only the one in use.
+pub(super) fn get_keep_versions(language_server_id: &str, worktree: &Worktree) -> usize {
+    get_settings(language_server_id, worktree)
+        .and_then(|settings| settings.get("keep_versions")?.as_u64())
+        .map_or(1, |keep_versions| keep_versions.max(1) as usize)
+}
+
+/// Checks the `settings` and `initialization_options` against the server's schema, returning a
+/// message for every unknown key or mistyped value.
+pub(super) fn invalid_settings(language_server_id: &str, worktree: &Worktree) -> Vec<String> {
+    let Ok(lsp_settings) = LspSettings::for_worktree(language_server_id, worktree) else {
+        return Vec::new();
+    };
+
+    [
+        ("settings", lsp_settings.settings),
+        (
+            "initialization_options",
+            lsp_settings.initialization_options,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(section, value)| Some(schema::validate(language_server_id, section, &value?)))
+    .flatten()
+    .collect()
+}
+
+fn get_settings(language_server_id: &str, worktree: &Worktree) -> Option<Value> {
     LspSettings::for_worktree(language_server_id, worktree)
         .ok()
         .and_then(|lsp_settings| lsp_settings.settings)
@@ -42,3 +159,96 @@ pub(super) fn get_binary_args(binary_settings: &Option<CommandSettings>) -> Opti
         .as_ref()
         .and_then(|binary_settings| binary_settings.arguments.clone())
 }
+
+/// Returns the worktree's shell environment, overridden by the `binary.env` settings.
+pub(super) fn get_command_env(language_server_id: &str, worktree: &Worktree) -> EnvVars {
+    let mut env = worktree.shell_env();
+
+    let overrides = get_binary_settings(language_server_id, worktree)
+        .and_then(|binary_settings| binary_settings.env)
+        .unwrap_or_default();
+    for (key, value) in overrides {
+        match env
+            .iter_mut()
+            .find(|(existing_key, _)| *existing_key == key)
+        {
+            Some((_, existing_value)) => *existing_value = value,
+            None => env.push((key, value)),
+        }
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use zed_extension_api::serde_json::json;
+
+    use super::*;
+
+    fn merged(mut defaults: Value, overrides: Value) -> Value {
+        merge(&mut defaults, overrides);
+        defaults
+    }
+
+    #[test]
+    fn merges_nested_overrides() {
+        assert_eq!(
+            merged(
+                json!({"experimental": {"completions": {"enable": false}, "dialyzer": true}}),
+                json!({"experimental": {"completions": {"enable": true}}, "mixEnv": "test"}),
+            ),
+            json!({
+                "experimental": {"completions": {"enable": true}, "dialyzer": true},
+                "mixEnv": "test"
+            })
+        );
+    }
+
+    #[test]
+    fn null_removes_a_default() {
+        assert_eq!(
+            merged(
+                json!({"workspaceSymbols": {"enabled": true}, "logLevel": "info"}),
+                json!({"workspaceSymbols": {"enabled": null}, "logLevel": null}),
+            ),
+            json!({"workspaceSymbols": {}})
+        );
+    }
+
+    #[test]
+    fn null_without_a_default_is_not_forwarded() {
+        assert_eq!(
+            merged(
+                json!({"logLevel": "info"}),
+                json!({"experimental": {"foo": null, "bar": {"baz": null}}, "mixEnv": null}),
+            ),
+            json!({"logLevel": "info", "experimental": {"bar": {}}})
+        );
+        assert_eq!(
+            merged(
+                json!({"experimental": false}),
+                json!({"experimental": {"foo": null}})
+            ),
+            json!({"experimental": {}})
+        );
+    }
+
+    #[test]
+    fn values_replace_objects() {
+        assert_eq!(
+            merged(
+                json!({"experimental": {"completions": {"enable": true}}}),
+                json!({"experimental": false}),
+            ),
+            json!({"experimental": false})
+        );
+        assert_eq!(
+            merged(
+                json!({"experimental": false}),
+                json!({"experimental": {"a": 1}})
+            ),
+            json!({"experimental": {"a": 1}})
+        );
+    }
+}
@@ -1,18 +1,93 @@
 use std::{env, fs, str::FromStr};
 
 use zed_extension_api::{
-    self as zed, CodeLabel, CodeLabelSpan, DebugAdapterBinary, DebugConfig, DebugRequest,
-    DebugScenario, DebugTaskDefinition, LanguageServerId, Result, StartDebuggingRequestArguments,
+    self as zed, CodeLabel, DebugAdapterBinary, DebugConfig, DebugRequest, DebugScenario,
+    DebugTaskDefinition, LanguageServerId, Result, StartDebuggingRequestArguments,
     StartDebuggingRequestArgumentsRequest, Worktree,
-    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
+    lsp::CompletionKind,
     serde_json::{Map, Value, json},
 };
 
-use crate::language_servers::{config, util};
+use crate::language_servers::{
+    LanguageServer,
+    binary::{BinaryLookup, LanguageServerBinary},
+    config,
+    installer::{GithubReleaseInstaller, InstalledRelease, ReleaseChannel},
+    labels::{self, CompletionItem},
+    toolchain::Toolchain,
+    version::Version,
+};
+
+/// The oldest Elixir and Erlang/OTP versions supported by ElixirLS releases, newest first. Each
+/// entry applies to releases up to and including `release`, where `None` stands for the latest one.
+const TOOLCHAIN_REQUIREMENTS: &[ToolchainRequirement] = &[
+    ToolchainRequirement {
+        release: None,
+        elixir: "1.14",
+        otp: 24,
+    },
+    ToolchainRequirement {
+        release: Some("v0.26.4"),
+        elixir: "1.13",
+        otp: 22,
+    },
+    ToolchainRequirement {
+        release: Some("v0.19.0"),
+        elixir: "1.12",
+        otp: 22,
+    },
+];
+
+struct ToolchainRequirement {
+    release: Option<&'static str>,
+    elixir: &'static str,
+    otp: u64,
+}
+
+impl ToolchainRequirement {
+    /// Unknown toolchain versions are assumed to be supported. Without a detected Elixir version,
+    /// the newest version `mix.exs` allows must still be supported, as the project can't run on
+    /// anything newer.
+    fn is_met_by(&self, toolchain: &Toolchain) -> bool {
+        let Some(min_elixir) = Version::parse(self.elixir) else {
+            return false;
+        };
+        let elixir = toolchain
+            .elixir
+            .as_ref()
+            .or(toolchain.newest_allowed_elixir.as_ref());
+
+        elixir.is_none_or(|elixir| *elixir >= min_elixir)
+            && toolchain.otp.is_none_or(|otp| otp >= self.otp)
+    }
+}
+
+/// Picks the newest release supporting the given toolchain, returning `None` when the latest
+/// release does.
+fn compatible_release(toolchain: &Toolchain) -> Result<Option<&'static str>> {
+    if let Some(requirement) = TOOLCHAIN_REQUIREMENTS
+        .iter()
+        .find(|requirement| requirement.is_met_by(toolchain))
+    {
+        return Ok(requirement.release);
+    }
 
-struct ElixirLsBinary {
-    path: String,
-    args: Vec<String>,
+    let oldest = TOOLCHAIN_REQUIREMENTS
+        .last()
+        .ok_or("no ElixirLS toolchain requirements")?;
+    let elixir = match (&toolchain.elixir, &toolchain.newest_allowed_elixir) {
+        (Some(elixir), _) => elixir.to_string(),
+        (None, Some(newest_allowed)) => format!("{newest_allowed} or older (from mix.exs)"),
+        (None, None) => "unknown".to_string(),
+    };
+    let otp = toolchain
+        .otp
+        .map_or("unknown".to_string(), |otp| otp.to_string());
+
+    Err(format!(
+        "ElixirLS requires Elixir >= {} and Erlang/OTP >= {}, but this project uses Elixir {elixir} and Erlang/OTP {otp}",
+        oldest.elixir, oldest.otp
+    ))
 }
 
 pub struct ElixirLs {
@@ -32,292 +107,89 @@ impl ElixirLs {
         }
     }
 
-    fn download_elixir_ls(
-        &mut self,
-        language_server_id: Option<&LanguageServerId>,
-    ) -> Result<(String, String)> {
-        let (platform, _arch) = zed::current_platform();
-        let extension = match platform {
-            zed::Os::Mac | zed::Os::Linux => ".sh",
-            zed::Os::Windows => ".bat",
-        };
-
-        let language_server = format!("language_server{extension}");
-        let launch_script = format!("launch{extension}");
-        let debug_adapter = format!("debug_adapter{extension}");
-
-        if let Some(language_server_id) = language_server_id {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::CheckingForUpdate,
-            );
-        }
-
-        let release = match zed::latest_github_release(
-            "elixir-lsp/elixir-ls",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        ) {
-            Ok(release) => release,
-            Err(_) => {
-                if let Some(lsp_binary_path) =
-                    util::find_existing_binary(Self::LANGUAGE_SERVER_ID, &language_server)
-                    && let Some(dap_binary_path) = env::current_dir()
-                        .map_err(|e| format!("failed to resolve debug adapter path: {e}"))?
-                        .join(lsp_binary_path.clone())
-                        .parent()
-                        .map(|path| path.join(debug_adapter).to_string_lossy().to_string())
-                {
-                    self.cached_lsp_binary_path = Some(lsp_binary_path.clone());
-                    self.cached_dap_binary_path = Some(dap_binary_path.clone());
-                    return Ok((lsp_binary_path, dap_binary_path));
-                }
-                return Err("failed to download latest github release".to_string());
-            }
-        };
-
-        let asset_name = format!(
-            "{}-{version}.zip",
-            Self::LANGUAGE_SERVER_ID,
-            version = release.version,
-        );
-
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("{}-{}", Self::LANGUAGE_SERVER_ID, release.version);
-        let lsp_binary_path = format!("{}/{}", version_dir, language_server);
-        let launch_binary_path = format!("{}/{}", version_dir, launch_script);
-        let dap_binary_path = format!("{}/{}", version_dir, debug_adapter);
-
-        if !fs::metadata(&lsp_binary_path).is_ok_and(|stat| stat.is_file()) {
-            if let Some(language_server_id) = language_server_id {
-                zed::set_language_server_installation_status(
-                    language_server_id,
-                    &zed::LanguageServerInstallationStatus::Downloading,
-                );
-            }
-
-            zed::download_file(
-                &asset.download_url,
-                &version_dir,
-                zed::DownloadedFileType::Zip,
-            )
-            .map_err(|e| format!("failed to download file: {e}"))?;
-
-            zed::make_file_executable(&lsp_binary_path)?;
-            zed::make_file_executable(&launch_binary_path)?;
-            zed::make_file_executable(&dap_binary_path)?;
-
-            util::remove_outdated_versions(Self::LANGUAGE_SERVER_ID, &version_dir)?;
-        }
-
-        let dap_binary_path = env::current_dir()
-            .map_err(|e| format!("failed to resolve debug adapter path: {e}"))?
-            .join(dap_binary_path)
-            .to_string_lossy()
-            .to_string();
-        self.cached_lsp_binary_path = Some(lsp_binary_path.clone());
-        self.cached_dap_binary_path = Some(dap_binary_path.clone());
-        Ok((lsp_binary_path, dap_binary_path))
-    }
-
-    pub fn language_server_command(
-        &mut self,
-        language_server_id: &LanguageServerId,
-        worktree: &Worktree,
-    ) -> Result<zed::Command> {
-        let elixir_ls = self.language_server_binary(language_server_id, worktree)?;
-
-        Ok(zed::Command {
-            command: elixir_ls.path,
-            args: elixir_ls.args,
-            env: Default::default(),
+    fn installer(worktree: &Worktree) -> Result<GithubReleaseInstaller> {
+        let extension = Self::script_extension();
+
+        Ok(GithubReleaseInstaller {
+            repo: "elixir-lsp/elixir-ls",
+            asset_name: format!("{}-{{version}}.zip", Self::LANGUAGE_SERVER_ID),
+            file_type: zed::DownloadedFileType::Zip,
+            binary_name: format!("language_server{extension}"),
+            executables: vec![
+                format!("launch{extension}"),
+                format!("debug_adapter{extension}"),
+            ],
+            ..GithubReleaseInstaller::new(
+                Self::LANGUAGE_SERVER_ID,
+                worktree,
+                ReleaseChannel::WITHOUT_NIGHTLY,
+            )?
         })
     }
 
-    fn language_server_binary(
-        &mut self,
-        language_server_id: &LanguageServerId,
+    /// Pins the installer to the newest release supporting the project's toolchain, unless a version
+    /// or another channel is configured.
+    ///
+    /// Detecting the toolchain runs `elixir --version`, so this is only done once a release actually
+    /// has to be installed, not when a previously installed one is reused.
+    fn pin_compatible_release(
+        mut installer: GithubReleaseInstaller,
         worktree: &Worktree,
-    ) -> Result<ElixirLsBinary> {
-        let binary_settings = config::get_binary_settings(Self::LANGUAGE_SERVER_ID, worktree);
-        let binary_args = config::get_binary_args(&binary_settings).unwrap_or_default();
-
-        if let Some(binary_path) = config::get_binary_path(&binary_settings) {
-            return Ok(ElixirLsBinary {
-                path: binary_path,
-                args: binary_args,
-            });
-        }
-
-        if let Some(binary_path) = worktree.which(Self::LANGUAGE_SERVER_ID) {
-            return Ok(ElixirLsBinary {
-                path: binary_path,
-                args: binary_args,
-            });
+    ) -> Result<GithubReleaseInstaller> {
+        if installer.version.is_none() && installer.channel == ReleaseChannel::Stable {
+            installer.version =
+                compatible_release(&Toolchain::detect(worktree))?.map(str::to_string);
         }
+        Ok(installer)
+    }
 
-        if let Some(binary_path) = &self.cached_lsp_binary_path
-            && fs::metadata(binary_path).is_ok_and(|stat| stat.is_file())
-        {
-            return Ok(ElixirLsBinary {
-                path: binary_path.clone(),
-                args: binary_args,
-            });
+    fn script_extension() -> &'static str {
+        let (platform, _arch) = zed::current_platform();
+        match platform {
+            zed::Os::Mac | zed::Os::Linux => ".sh",
+            zed::Os::Windows => ".bat",
         }
-
-        let (binary_path, _) = self.download_elixir_ls(Some(language_server_id))?;
-        Ok(ElixirLsBinary {
-            path: binary_path,
-            args: binary_args,
-        })
     }
 
-    pub fn language_server_initialization_options(
-        &mut self,
-        _worktree: &Worktree,
-    ) -> Result<Option<Value>> {
-        Ok(None)
+    /// Returns the absolute path of the debug adapter shipped with an installed release.
+    fn dap_binary_path(installed: &InstalledRelease) -> Result<String> {
+        Ok(env::current_dir()
+            .map_err(|e| format!("failed to resolve debug adapter path: {e}"))?
+            .join(&installed.version_dir)
+            .join(format!("debug_adapter{}", Self::script_extension()))
+            .to_string_lossy()
+            .to_string())
     }
 
-    pub fn language_server_workspace_configuration(
+    fn language_server_binary(
         &mut self,
+        language_server_id: &LanguageServerId,
         worktree: &Worktree,
-    ) -> Result<Option<Value>> {
-        let settings = config::get_workspace_configuration(Self::LANGUAGE_SERVER_ID, worktree)
-            .unwrap_or_default();
-
-        Ok(Some(json!({
-            "elixirLS": settings
-        })))
-    }
-
-    pub fn label_for_completion(&self, completion: Completion) -> Option<CodeLabel> {
-        let name = &completion.label;
-        let detail = completion
-            .detail
-            .filter(|detail| detail != "alias")
-            .map(|detail| format!(": {detail}"))
-            .unwrap_or("".to_string());
-
-        let detail_span = CodeLabelSpan::literal(detail, Some("comment.unused".to_string()));
-
-        match completion.kind? {
-            CompletionKind::Module | CompletionKind::Class | CompletionKind::Struct => {
-                let defmodule = "defmodule ";
-                let alias = completion
-                    .label_details
-                    .and_then(|details| details.description)
-                    .filter(|description| description.starts_with("alias"))
-                    .map(|description| format!(" ({description})"))
-                    .unwrap_or("".to_string());
-
-                let code = format!("{defmodule}{name}{alias}");
-                let name_start = defmodule.len();
-                let name_end = name_start + name.len();
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![
-                        CodeLabelSpan::code_range(name_start..name_end),
-                        detail_span,
-                        CodeLabelSpan::code_range(name_end..(name_end + alias.len())),
-                    ],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            CompletionKind::Interface => Some(CodeLabel {
-                code: name.to_string(),
-                spans: vec![CodeLabelSpan::code_range(0..name.len()), detail_span],
-                filter_range: (0..name.len()).into(),
-            }),
-            CompletionKind::Field => Some(CodeLabel {
-                code: name.to_string(),
-                spans: vec![
-                    CodeLabelSpan::literal(name, Some("function".to_string())),
-                    detail_span,
-                ],
-                filter_range: (0..name.len()).into(),
-            }),
-            CompletionKind::Function | CompletionKind::Constant => {
-                let detail = completion
-                    .label_details
-                    .clone()
-                    .and_then(|details| details.detail)
-                    .unwrap_or("".to_string());
-
-                let description = completion
-                    .label_details
-                    .and_then(|details| details.description)
-                    .map(|description| format!(" ({description})"))
-                    .unwrap_or("".to_string());
-
-                let def = "def ";
-                let code = format!("{def}{name}{detail}{description}");
-
-                let name_start = def.len();
-                let name_end = name_start + name.len();
-                let detail_end = name_end + detail.len();
-                let description_end = detail_end + description.len();
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![
-                        CodeLabelSpan::code_range(name_start..name_end),
-                        CodeLabelSpan::code_range(name_end..detail_end),
-                        CodeLabelSpan::code_range(detail_end..description_end),
-                    ],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            CompletionKind::Operator => {
-                let def_a = "def a ";
-                let code = format!("{def_a}{name} b");
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![CodeLabelSpan::code_range(
-                        def_a.len()..def_a.len() + name.len(),
-                    )],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            _ => None,
-        }
-    }
-
-    pub fn label_for_symbol(&self, symbol: Symbol) -> Option<CodeLabel> {
-        let name = &symbol.name;
-
-        let (code, filter_range, display_range) = match symbol.kind {
-            SymbolKind::Module | SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct => {
-                let defmodule = "defmodule ";
-                let code = format!("{defmodule}{name}");
-                let filter_range = 0..name.len();
-                let display_range = defmodule.len()..defmodule.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            SymbolKind::Function | SymbolKind::Constant => {
-                let def = "def ";
-                let code = format!("{def}{name}");
-                let filter_range = 0..name.len();
-                let display_range = def.len()..def.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            _ => return None,
+    ) -> Result<LanguageServerBinary> {
+        let report_failure = |error: &String| {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Failed(error.clone()),
+            );
         };
 
-        Some(CodeLabel {
-            spans: vec![CodeLabelSpan::code_range(display_range)],
-            filter_range: filter_range.into(),
-            code,
-        })
+        BinaryLookup {
+            language_server_id: Self::LANGUAGE_SERVER_ID,
+            binary_name: Self::LANGUAGE_SERVER_ID,
+            default_args: &[],
+        }
+        .find_or_install(
+            worktree,
+            &mut self.cached_lsp_binary_path,
+            || Self::installer(worktree).inspect_err(report_failure),
+            |installer| {
+                let installed = Self::pin_compatible_release(installer, worktree)
+                    .inspect_err(report_failure)?
+                    .install(Some(language_server_id))?;
+                self.cached_dap_binary_path = Some(Self::dap_binary_path(&installed)?);
+                Ok(installed)
+            },
+        )
     }
 
     pub fn get_dap_binary(
@@ -361,13 +233,19 @@ impl ElixirLs {
             return Ok(binary_path);
         }
 
+        let installer = Self::installer(worktree)?;
+
         if let Some(binary_path) = &self.cached_dap_binary_path
+            && installer.manages(binary_path)
             && fs::metadata(binary_path).is_ok_and(|stat| stat.is_file())
         {
             return Ok(binary_path.clone());
         }
 
-        let (_, binary_path) = self.download_elixir_ls(None)?;
+        let installed = Self::pin_compatible_release(installer, worktree)?.install(None)?;
+        let binary_path = Self::dap_binary_path(&installed)?;
+        self.cached_lsp_binary_path = Some(installed.binary_path);
+        self.cached_dap_binary_path = Some(binary_path.clone());
         Ok(binary_path)
     }
 
@@ -424,3 +302,97 @@ impl ElixirLs {
         })
     }
 }
+
+impl LanguageServer for ElixirLs {
+    fn language_server_command(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<zed::Command> {
+        let elixir_ls = self.language_server_binary(language_server_id, worktree)?;
+
+        Ok(zed::Command {
+            command: elixir_ls.path,
+            args: elixir_ls.args,
+            env: config::get_command_env(Self::LANGUAGE_SERVER_ID, worktree),
+        })
+    }
+
+    fn language_server_workspace_configuration(
+        &mut self,
+        worktree: &Worktree,
+    ) -> Result<Option<Value>> {
+        let settings = config::get_workspace_configuration(Self::LANGUAGE_SERVER_ID, worktree)
+            .unwrap_or_default();
+
+        Ok(Some(json!({
+            "elixirLS": settings
+        })))
+    }
+
+    fn label_for_completion(&self, completion: &CompletionItem) -> Option<CodeLabel> {
+        match completion.kind? {
+            // Modules that are aliased in scope are described as `alias Foo.Bar`, while other
+            // modules carry a bare `alias` detail.
+            CompletionKind::Module | CompletionKind::Class => {
+                let detail = completion
+                    .label_description
+                    .filter(|description| description.starts_with("alias"))
+                    .or(completion.detail)
+                    .filter(|detail| *detail != "alias");
+
+                Some(labels::module(completion.label, detail))
+            }
+            _ => labels::completion(completion),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(elixir: Option<&str>, otp: Option<u64>, newest_allowed: Option<&str>) -> String {
+        let toolchain = Toolchain {
+            elixir: elixir.and_then(Version::parse),
+            otp,
+            newest_allowed_elixir: newest_allowed.and_then(Version::parse),
+        };
+        match compatible_release(&toolchain) {
+            Ok(release) => release.unwrap_or("latest").to_string(),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn installs_the_latest_release_for_recent_or_unknown_toolchains() {
+        assert_eq!(release(Some("1.17.3"), Some(27), None), "latest");
+        assert_eq!(release(None, None, None), "latest");
+        assert_eq!(release(Some("1.14.0"), None, Some("1.12")), "latest");
+    }
+
+    #[test]
+    fn installs_the_newest_release_supporting_older_toolchains() {
+        assert_eq!(release(Some("1.13.4"), Some(25), None), "v0.26.4");
+        assert_eq!(release(Some("1.16.0"), Some(23), None), "v0.26.4");
+        assert_eq!(release(Some("1.12.3"), None, None), "v0.19.0");
+    }
+
+    #[test]
+    fn installs_the_newest_release_mix_exs_allows() {
+        assert_eq!(release(None, None, Some("1.13")), "v0.26.4");
+        assert_eq!(release(None, Some(24), Some("1.12.3")), "v0.19.0");
+    }
+
+    #[test]
+    fn rejects_toolchains_no_release_supports() {
+        assert_eq!(
+            release(Some("1.11.4"), Some(23), None),
+            "ElixirLS requires Elixir >= 1.12 and Erlang/OTP >= 22, but this project uses Elixir 1.11.4 and Erlang/OTP 23"
+        );
+        assert_eq!(
+            release(None, None, Some("1.11")),
+            "ElixirLS requires Elixir >= 1.12 and Erlang/OTP >= 22, but this project uses Elixir 1.11 or older (from mix.exs) and Erlang/OTP unknown"
+        );
+    }
+}
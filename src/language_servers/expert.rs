@@ -1,17 +1,14 @@
-use std::fs;
-
 use zed_extension_api::{
-    self as zed, CodeLabel, CodeLabelSpan, LanguageServerId, Result, Worktree,
-    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
-    serde_json::Value,
+    self as zed, LanguageServerId, Result, Worktree,
+    serde_json::{Value, json},
 };
 
-use crate::language_servers::{config, util};
-
-struct ExpertBinary {
-    path: String,
-    args: Vec<String>,
-}
+use crate::language_servers::{
+    LanguageServer,
+    binary::{BinaryLookup, LanguageServerBinary},
+    config,
+    installer::{GithubReleaseInstaller, ReleaseChannel},
+};
 
 pub struct Expert {
     cached_binary_path: Option<String>,
@@ -26,7 +23,58 @@ impl Expert {
         }
     }
 
-    pub fn language_server_command(
+    fn language_server_binary(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<LanguageServerBinary> {
+        BinaryLookup {
+            language_server_id: Self::LANGUAGE_SERVER_ID,
+            binary_name: Self::LANGUAGE_SERVER_ID,
+            default_args: &["--stdio"],
+        }
+        .find_or_install(
+            worktree,
+            &mut self.cached_binary_path,
+            || Self::installer(worktree),
+            |installer| installer.install(Some(language_server_id)),
+        )
+    }
+
+    fn installer(worktree: &Worktree) -> Result<GithubReleaseInstaller> {
+        let (platform, arch) = zed::current_platform();
+
+        Ok(GithubReleaseInstaller {
+            repo: "expert-lsp/expert",
+            asset_name: format!(
+                "{}_{os}_{arch}{extension}",
+                Self::LANGUAGE_SERVER_ID,
+                os = match platform {
+                    zed::Os::Mac => "darwin",
+                    zed::Os::Linux => "linux",
+                    zed::Os::Windows => "windows",
+                },
+                arch = match arch {
+                    zed::Architecture::Aarch64 => "arm64",
+                    zed::Architecture::X8664 => "amd64",
+                    zed::Architecture::X86 =>
+                        return Err(format!("unsupported architecture: {arch:?}")),
+                },
+                extension = match platform {
+                    zed::Os::Mac | zed::Os::Linux => "",
+                    zed::Os::Windows => ".exe",
+                },
+            ),
+            file_type: zed::DownloadedFileType::Uncompressed,
+            binary_name: Self::LANGUAGE_SERVER_ID.to_string(),
+            executables: vec![],
+            ..GithubReleaseInstaller::new(Self::LANGUAGE_SERVER_ID, worktree, ReleaseChannel::ALL)?
+        })
+    }
+}
+
+impl LanguageServer for Expert {
+    fn language_server_command(
         &mut self,
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
@@ -36,135 +84,30 @@ impl Expert {
         Ok(zed::Command {
             command: expert.path,
             args: expert.args,
-            env: Default::default(),
+            env: config::get_command_env(Self::LANGUAGE_SERVER_ID, worktree),
         })
     }
 
-    fn language_server_binary(
+    fn language_server_initialization_options(
         &mut self,
-        language_server_id: &LanguageServerId,
         worktree: &Worktree,
-    ) -> Result<ExpertBinary> {
-        let (platform, arch) = zed::current_platform();
-
-        let binary_settings = config::get_binary_settings(Self::LANGUAGE_SERVER_ID, worktree);
-        let binary_args = config::get_binary_args(&binary_settings)
-            .unwrap_or_else(|| vec!["--stdio".to_string()]);
-
-        if let Some(binary_path) = config::get_binary_path(&binary_settings) {
-            return Ok(ExpertBinary {
-                path: binary_path,
-                args: binary_args,
-            });
-        }
-
-        if let Some(binary_path) = worktree.which(Self::LANGUAGE_SERVER_ID) {
-            return Ok(ExpertBinary {
-                path: binary_path,
-                args: binary_args,
-            });
-        }
-
-        if let Some(binary_path) = &self.cached_binary_path
-            && fs::metadata(binary_path).is_ok_and(|stat| stat.is_file())
-        {
-            return Ok(ExpertBinary {
-                path: binary_path.clone(),
-                args: binary_args,
-            });
-        }
-
-        zed::set_language_server_installation_status(
-            language_server_id,
-            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
-        );
-
-        let release = match zed::latest_github_release(
-            "expert-lsp/expert",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        ) {
-            Ok(release) => release,
-            Err(_) => {
-                if let Some(binary_path) =
-                    util::find_existing_binary(Self::LANGUAGE_SERVER_ID, Self::LANGUAGE_SERVER_ID)
-                {
-                    self.cached_binary_path = Some(binary_path.clone());
-                    return Ok(ExpertBinary {
-                        path: binary_path,
-                        args: binary_args,
-                    });
-                }
-                return Err("failed to download latest github release".to_string());
-            }
-        };
-
-        let asset_name = format!(
-            "{}_{os}_{arch}{extension}",
+    ) -> Result<Option<Value>> {
+        let settings = config::get_initialization_options(
             Self::LANGUAGE_SERVER_ID,
-            os = match platform {
-                zed::Os::Mac => "darwin",
-                zed::Os::Linux => "linux",
-                zed::Os::Windows => "windows",
-            },
-            arch = match arch {
-                zed::Architecture::Aarch64 => "arm64",
-                zed::Architecture::X8664 => "amd64",
-                zed::Architecture::X86 =>
-                    return Err(format!("unsupported architecture: {arch:?}")),
-            },
-            extension = match platform {
-                zed::Os::Mac | zed::Os::Linux => "",
-                zed::Os::Windows => ".exe",
-            },
+            worktree,
+            json!({
+                "logLevel": "info",
+                "workspaceSymbols": {
+                    "enabled": true
+                },
+                "buildDirectory": ".expert"
+            }),
         );
 
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("{}-{}", Self::LANGUAGE_SERVER_ID, release.version);
-        fs::create_dir_all(&version_dir).map_err(|e| format!("failed to create directory: {e}"))?;
-
-        let binary_path = format!("{}/{}", version_dir, Self::LANGUAGE_SERVER_ID);
-
-        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::Downloading,
-            );
-
-            zed::download_file(
-                &asset.download_url,
-                &binary_path,
-                zed::DownloadedFileType::Uncompressed,
-            )
-            .map_err(|e| format!("failed to download file: {e}"))?;
-
-            zed::make_file_executable(&binary_path)?;
-
-            util::remove_outdated_versions(Self::LANGUAGE_SERVER_ID, &version_dir)?;
-        }
-
-        self.cached_binary_path = Some(binary_path.clone());
-        Ok(ExpertBinary {
-            path: binary_path,
-            args: binary_args,
-        })
-    }
-
-    pub fn language_server_initialization_options(
-        &mut self,
-        _worktree: &Worktree,
-    ) -> Result<Option<Value>> {
-        Ok(None)
+        Ok(Some(settings))
     }
 
-    pub fn language_server_workspace_configuration(
+    fn language_server_workspace_configuration(
         &mut self,
         worktree: &Worktree,
     ) -> Result<Option<Value>> {
@@ -173,68 +116,4 @@ impl Expert {
 
         Ok(Some(settings))
     }
-
-    pub fn label_for_completion(&self, completion: Completion) -> Option<CodeLabel> {
-        match completion.kind? {
-            CompletionKind::Module
-            | CompletionKind::Class
-            | CompletionKind::Interface
-            | CompletionKind::Struct => {
-                let name = completion.label;
-
-                let detail = completion
-                    .detail
-                    .map(|detail| format!(" ({detail})"))
-                    .unwrap_or_default();
-
-                let defmodule = "defmodule ";
-                let heredoc_start = r#"@doc """\n"#;
-                let heredoc_end = r#"\n""""#;
-                let code = format!("{defmodule}{name}{heredoc_start}{detail}{heredoc_end}");
-
-                let name_start = defmodule.len();
-                let name_end = name_start + name.len();
-                let detail_start = name_end + heredoc_start.len();
-                let detail_end = detail_start + detail.len();
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![
-                        CodeLabelSpan::code_range(name_start..name_end),
-                        CodeLabelSpan::code_range(detail_start..detail_end),
-                    ],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            _ => None,
-        }
-    }
-
-    pub fn label_for_symbol(&self, symbol: Symbol) -> Option<CodeLabel> {
-        let name = &symbol.name;
-
-        let (code, filter_range, display_range) = match symbol.kind {
-            SymbolKind::Module | SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct => {
-                let defmodule = "defmodule ";
-                let code = format!("{defmodule}{name}");
-                let filter_range = 0..name.len();
-                let display_range = defmodule.len()..defmodule.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            SymbolKind::Function | SymbolKind::Constant => {
-                let def = "def ";
-                let code = format!("{def}{name}");
-                let filter_range = 0..name.len();
-                let display_range = def.len()..def.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            _ => return None,
-        };
-
-        Some(CodeLabel {
-            spans: vec![CodeLabelSpan::code_range(display_range)],
-            filter_range: filter_range.into(),
-            code,
-        })
-    }
 }
@@ -0,0 +1,497 @@
+use std::{fs, path::Path, str::FromStr};
+
+use zed_extension_api::{self as zed, LanguageServerId, Result, Worktree};
+
+use crate::language_servers::{checksum, config, util, version::Version};
+
+/// The release channel a language server is installed from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum ReleaseChannel {
+    #[default]
+    Stable,
+    Prerelease,
+    Nightly,
+}
+
+impl ReleaseChannel {
+    pub const ALL: &[Self] = &[Self::Stable, Self::Prerelease, Self::Nightly];
+    /// The channels of servers that don't publish a `nightly` release.
+    pub const WITHOUT_NIGHTLY: &[Self] = &[Self::Stable, Self::Prerelease];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Prerelease => "prerelease",
+            Self::Nightly => "nightly",
+        }
+    }
+}
+
+impl FromStr for ReleaseChannel {
+    type Err = String;
+
+    fn from_str(channel: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.name() == channel)
+            .ok_or_else(|| format!("unknown release channel: {channel:?}"))
+    }
+}
+
+/// Describes how a language server is published as a GitHub release asset.
+pub(super) struct GithubReleaseInstaller {
+    /// The prefix of the `<language_server_id>-<version>` directories releases are installed into.
+    pub language_server_id: &'static str,
+    /// The repository in the form `<owner>/<repo>`.
+    pub repo: &'static str,
+    /// The name of the release asset, with `{version}` standing in for the release version.
+    pub asset_name: String,
+    /// How the downloaded asset is unpacked.
+    pub file_type: zed::DownloadedFileType,
+    /// The path of the language server binary inside the version directory.
+    pub binary_name: String,
+    /// Other files inside the version directory that must be marked as executable.
+    pub executables: Vec<String>,
+    /// The release tag to install instead of the latest release.
+    pub version: Option<String>,
+    /// The channel releases are picked from when no version is pinned.
+    pub channel: ReleaseChannel,
+    /// How many installed versions of a channel to keep around.
+    pub keep_versions: usize,
+}
+
+pub(super) struct InstalledRelease {
+    pub version_dir: String,
+    pub binary_path: String,
+}
+
+impl GithubReleaseInstaller {
+    /// Reads the release `version`, `channel` and `keep_versions` a worktree configures for a
+    /// language server, rejecting channels it doesn't publish.
+    ///
+    /// The server describes its release assets by overriding the remaining fields:
+    ///
+    /// ```ignore
+    /// GithubReleaseInstaller {
+    ///     repo: "owner/repo",
+    ///     asset_name: "server-{version}.zip".to_string(),
+    ///     ..GithubReleaseInstaller::new("server", worktree, ReleaseChannel::ALL)?
+    /// }
+    /// ```
+    pub fn new(
+        language_server_id: &'static str,
+        worktree: &Worktree,
+        supported_channels: &[ReleaseChannel],
+    ) -> Result<Self> {
+        Ok(Self {
+            language_server_id,
+            repo: "",
+            asset_name: String::new(),
+            file_type: zed::DownloadedFileType::Uncompressed,
+            binary_name: language_server_id.to_string(),
+            executables: Vec::new(),
+            version: config::get_version(language_server_id, worktree),
+            channel: config::get_release_channel(language_server_id, worktree, supported_channels)?,
+            keep_versions: config::get_keep_versions(language_server_id, worktree),
+        })
+    }
+
+    /// Installs the pinned or latest release, falling back to a previously installed one when offline.
+    pub fn install(
+        &self,
+        language_server_id: Option<&LanguageServerId>,
+    ) -> Result<InstalledRelease> {
+        if let Some(version) = &self.version {
+            return self.install_version(language_server_id, version);
+        }
+
+        if self.channel == ReleaseChannel::Nightly {
+            return self.install_nightly(language_server_id);
+        }
+
+        set_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+
+        let release = match zed::latest_github_release(
+            self.repo,
+            zed::GithubReleaseOptions {
+                require_assets: true,
+                pre_release: self.channel == ReleaseChannel::Prerelease,
+            },
+        ) {
+            Ok(release) => release,
+            Err(_) => {
+                return self
+                    .find_existing_release()
+                    .ok_or_else(|| "failed to download latest github release".to_string());
+            }
+        };
+
+        let version_dir = format!("{}{}", self.version_dir_prefix(), release.version);
+        self.install_release(language_server_id, release, version_dir)
+            .or_else(|error| self.roll_back(language_server_id, error))
+    }
+
+    /// Returns whether the given path lies in a directory installed for the configured version and channel.
+    pub fn manages(&self, path: &str) -> bool {
+        Path::new(path).components().any(|component| {
+            component
+                .as_os_str()
+                .to_str()
+                .is_some_and(|version_dir| self.is_version_dir(version_dir))
+        })
+    }
+
+    fn install_version(
+        &self,
+        language_server_id: Option<&LanguageServerId>,
+        version: &str,
+    ) -> Result<InstalledRelease> {
+        let version_dir = format!("{}-{}", self.language_server_id, version);
+        let installed = self.installed_release(version_dir.clone());
+        if self.is_complete(&installed) {
+            return Ok(installed);
+        }
+
+        set_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+
+        let release = zed::github_release_by_tag_name(self.repo, version)
+            .map_err(|e| format!("failed to fetch github release {version}: {e}"))?;
+
+        self.install_release(language_server_id, release, version_dir)
+    }
+
+    /// Nightly builds are republished under the same tag, so they are installed into a directory
+    /// named after the current date and refreshed at most once a day.
+    fn install_nightly(
+        &self,
+        language_server_id: Option<&LanguageServerId>,
+    ) -> Result<InstalledRelease> {
+        let version_dir = format!("{}{}", self.version_dir_prefix(), util::current_date());
+        let installed = self.installed_release(version_dir.clone());
+        if self.is_complete(&installed) {
+            return Ok(installed);
+        }
+
+        set_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+
+        let release = match zed::github_release_by_tag_name(self.repo, "nightly") {
+            Ok(release) => release,
+            Err(error) => {
+                return self
+                    .find_existing_release()
+                    .ok_or_else(|| format!("failed to download nightly github release: {error}"));
+            }
+        };
+
+        self.install_release(language_server_id, release, version_dir)
+            .or_else(|error| self.roll_back(language_server_id, error))
+    }
+
+    fn install_release(
+        &self,
+        language_server_id: Option<&LanguageServerId>,
+        release: zed::GithubRelease,
+        version_dir: String,
+    ) -> Result<InstalledRelease> {
+        let asset_name = self.asset_name.replace("{version}", &release.version);
+        let asset = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == asset_name)
+            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
+
+        let installed = self.installed_release(version_dir);
+
+        if !self.is_complete(&installed) {
+            set_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Downloading,
+            );
+
+            if let Err(error) = self
+                .published_checksum(&release.assets, &asset_name)
+                .and_then(|checksum| self.download_asset(asset, checksum.as_deref(), &installed))
+                .and_then(|()| self.validate(&installed))
+            {
+                fs::remove_dir_all(&installed.version_dir).ok();
+                return Err(format!("failed to install {}: {error}", release.version));
+            }
+
+            // Previous versions are only removed once the new one is known to be usable, so that
+            // a broken release can still fall back to them.
+            self.remove_outdated_versions(&installed)?;
+        }
+
+        Ok(installed)
+    }
+
+    /// Falls back to a previously installed release of the same channel when installing a new one
+    /// fails, reporting the failure through the installation status.
+    fn roll_back(
+        &self,
+        language_server_id: Option<&LanguageServerId>,
+        error: String,
+    ) -> Result<InstalledRelease> {
+        let Some(previous) = self.find_existing_release() else {
+            return Err(error);
+        };
+
+        set_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Failed(format!(
+                "{error}; falling back to {}",
+                previous.version_dir
+            )),
+        );
+
+        Ok(previous)
+    }
+
+    /// Returns the checksum to verify the asset against, if it can be verified at all.
+    ///
+    /// Archives are downloaded and extracted by Zed in one step, and the extension API can't
+    /// extract a file that is already on disk. Hashing a separate download of an archive would not
+    /// vouch for the bytes Zed extracts, so archives are installed unverified.
+    fn published_checksum(
+        &self,
+        assets: &[zed::GithubReleaseAsset],
+        asset_name: &str,
+    ) -> Result<Option<String>> {
+        match self.file_type {
+            zed::DownloadedFileType::Uncompressed => {
+                checksum::published_checksum(assets, asset_name)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Downloads the asset into the version directory. Uncompressed assets with a published
+    /// checksum are hashed while they are written to disk and removed if they don't match.
+    fn download_asset(
+        &self,
+        asset: &zed::GithubReleaseAsset,
+        expected_checksum: Option<&str>,
+        installed: &InstalledRelease,
+    ) -> Result<()> {
+        let download_path = match self.file_type {
+            zed::DownloadedFileType::Uncompressed => {
+                fs::create_dir_all(&installed.version_dir)
+                    .map_err(|e| format!("failed to create directory: {e}"))?;
+                &installed.binary_path
+            }
+            _ => &installed.version_dir,
+        };
+
+        if let Some(expected_checksum) = expected_checksum {
+            let checksum = checksum::fetch_sha256(&asset.download_url, download_path)?;
+            return checksum::verify(&asset.name, expected_checksum, &checksum);
+        }
+
+        zed::download_file(&asset.download_url, download_path, self.file_type)
+            .map_err(|e| format!("failed to download file: {e}"))
+    }
+
+    /// Checks that every expected file was unpacked and marks them as executable.
+    fn validate(&self, installed: &InstalledRelease) -> Result<()> {
+        for path in self.installed_files(installed) {
+            if !fs::metadata(&path).is_ok_and(|stat| stat.is_file()) {
+                return Err(format!("downloaded release is missing {path}"));
+            }
+            zed::make_file_executable(&path)?;
+        }
+        Ok(())
+    }
+
+    fn is_complete(&self, installed: &InstalledRelease) -> bool {
+        self.installed_files(installed)
+            .all(|path| fs::metadata(path).is_ok_and(|stat| stat.is_file()))
+    }
+
+    fn installed_files(&self, installed: &InstalledRelease) -> impl Iterator<Item = String> {
+        let executables = self
+            .executables
+            .iter()
+            .map(|executable| format!("{}/{}", installed.version_dir, executable));
+
+        std::iter::once(installed.binary_path.clone()).chain(executables)
+    }
+
+    /// Returns the most recent complete installation of the configured channel.
+    fn find_existing_release(&self) -> Option<InstalledRelease> {
+        self.installed_versions(self.channel)
+            .into_iter()
+            .map(|(_, version_dir)| self.installed_release(version_dir))
+            .find(|installed| self.is_complete(installed))
+    }
+
+    /// Removes all but the `keep_versions` most recent installations of the installed release's
+    /// channel, always keeping the installed release itself.
+    fn remove_outdated_versions(&self, installed: &InstalledRelease) -> Result<()> {
+        let Some((channel, _)) = self.parse_version_dir(&installed.version_dir) else {
+            return Ok(());
+        };
+
+        let outdated = self
+            .installed_versions(channel)
+            .into_iter()
+            .map(|(_, version_dir)| version_dir)
+            .filter(|version_dir| *version_dir != installed.version_dir)
+            .skip(self.keep_versions.saturating_sub(1));
+
+        util::remove_dirs(outdated);
+        Ok(())
+    }
+
+    /// Lists the version directories of the given channel, newest first.
+    fn installed_versions(&self, channel: ReleaseChannel) -> Vec<(Version, String)> {
+        let mut versions = util::list_dirs()
+            .into_iter()
+            .filter_map(|version_dir| {
+                let (dir_channel, version) = self.parse_version_dir(&version_dir)?;
+                (dir_channel == channel).then_some((version, version_dir))
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by(|(a, _), (b, _)| b.cmp(a));
+        versions
+    }
+
+    fn installed_release(&self, version_dir: String) -> InstalledRelease {
+        InstalledRelease {
+            binary_path: format!("{}/{}", version_dir, self.binary_name),
+            version_dir,
+        }
+    }
+
+    fn version_dir_prefix(&self) -> String {
+        match self.channel {
+            ReleaseChannel::Stable => format!("{}-", self.language_server_id),
+            channel => format!("{}-{}-", self.language_server_id, channel.name()),
+        }
+    }
+
+    fn is_version_dir(&self, version_dir: &str) -> bool {
+        match &self.version {
+            Some(version) => version_dir == format!("{}-{}", self.language_server_id, version),
+            None => self
+                .parse_version_dir(version_dir)
+                .is_some_and(|(channel, _)| channel == self.channel),
+        }
+    }
+
+    /// Parses a `<language_server_id>[-<channel>]-<version>` directory name, where releases of the
+    /// stable channel and pinned versions omit the channel.
+    fn parse_version_dir(&self, version_dir: &str) -> Option<(ReleaseChannel, Version)> {
+        let suffix = version_dir
+            .strip_prefix(self.language_server_id)?
+            .strip_prefix('-')?;
+
+        let (channel, version) = ReleaseChannel::ALL
+            .iter()
+            .copied()
+            .filter(|channel| *channel != ReleaseChannel::Stable)
+            .find_map(|channel| {
+                let version = suffix.strip_prefix(channel.name())?.strip_prefix('-')?;
+                Some((channel, version))
+            })
+            .unwrap_or((ReleaseChannel::Stable, suffix));
+
+        Some((channel, Version::parse(version)?))
+    }
+}
+
+fn set_installation_status(
+    language_server_id: Option<&LanguageServerId>,
+    status: &zed::LanguageServerInstallationStatus,
+) {
+    if let Some(language_server_id) = language_server_id {
+        zed::set_language_server_installation_status(language_server_id, status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installer(version: Option<&str>) -> GithubReleaseInstaller {
+        GithubReleaseInstaller {
+            language_server_id: "expert",
+            repo: "elixir-lang/expert",
+            asset_name: "expert_{version}".to_string(),
+            file_type: zed::DownloadedFileType::Uncompressed,
+            binary_name: "expert".to_string(),
+            executables: Vec::new(),
+            version: version.map(str::to_string),
+            channel: ReleaseChannel::Stable,
+            keep_versions: 1,
+        }
+    }
+
+    fn parse(version_dir: &str) -> Option<(ReleaseChannel, String)> {
+        installer(None)
+            .parse_version_dir(version_dir)
+            .map(|(channel, version)| (channel, version.to_string()))
+    }
+
+    #[test]
+    fn parses_stable_version_dirs() {
+        assert_eq!(
+            parse("expert-v0.1.0"),
+            Some((ReleaseChannel::Stable, "0.1.0".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_prerelease_version_dirs() {
+        assert_eq!(
+            parse("expert-prerelease-v0.2.0-rc.10"),
+            Some((ReleaseChannel::Prerelease, "0.2.0-rc.10".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_nightly_version_dirs() {
+        assert_eq!(
+            parse("expert-nightly-20250101"),
+            Some((ReleaseChannel::Nightly, "20250101".to_string()))
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_dirs() {
+        for version_dir in [
+            "expert",
+            "expert-",
+            "expertise-1.0.0",
+            "next-ls-v0.23.0",
+            "expert-nightly",
+            "expert-nightly-latest",
+            "expert-canary-20250101",
+        ] {
+            assert_eq!(parse(version_dir), None, "{version_dir:?}");
+        }
+    }
+
+    #[test]
+    fn pinned_versions_only_manage_their_own_dir() {
+        let installer = installer(Some("v0.1.0"));
+        assert!(installer.is_version_dir("expert-v0.1.0"));
+        assert!(!installer.is_version_dir("expert-v0.2.0"));
+        assert!(!installer.is_version_dir("expert-nightly-20250101"));
+        assert_eq!(
+            installer
+                .parse_version_dir("expert-v0.1.0")
+                .map(|(channel, _)| channel),
+            Some(ReleaseChannel::Stable)
+        );
+    }
+}
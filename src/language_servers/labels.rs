@@ -0,0 +1,521 @@
+use std::ops::Range;
+
+use zed_extension_api::{
+    CodeLabel, CodeLabelSpan,
+    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
+};
+
+/// The parts of an LSP completion that labels are built from.
+#[derive(Default)]
+pub struct CompletionItem<'a> {
+    pub label: &'a str,
+    pub kind: Option<CompletionKind>,
+    pub detail: Option<&'a str>,
+    /// `label_details.detail`, usually the signature or arity.
+    pub label_detail: Option<&'a str>,
+    /// `label_details.description`, usually the module the item comes from.
+    pub label_description: Option<&'a str>,
+}
+
+impl<'a> From<&'a Completion> for CompletionItem<'a> {
+    fn from(completion: &'a Completion) -> Self {
+        let label_details = completion.label_details.as_ref();
+        Self {
+            label: &completion.label,
+            kind: completion.kind,
+            detail: completion.detail.as_deref(),
+            label_detail: label_details.and_then(|details| details.detail.as_deref()),
+            label_description: label_details.and_then(|details| details.description.as_deref()),
+        }
+    }
+}
+
+/// Builds a [`CodeLabel`] span by span, keeping the highlighted code, the displayed spans and the
+/// filter range in sync.
+#[derive(Default)]
+pub(super) struct LabelBuilder {
+    code: String,
+    spans: Vec<CodeLabelSpan>,
+    display_len: usize,
+    filter_range: Option<Range<usize>>,
+}
+
+impl LabelBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends code that is only parsed for highlighting, such as the `def ` before a name.
+    pub fn hidden(mut self, code: &str) -> Self {
+        self.code.push_str(code);
+        self
+    }
+
+    /// Appends code that is displayed with its syntax highlighting.
+    pub fn code(mut self, code: &str) -> Self {
+        if code.is_empty() {
+            return self;
+        }
+        let start = self.code.len();
+        self.code.push_str(code);
+        self.spans
+            .push(CodeLabelSpan::code_range(start..self.code.len()));
+        self.display_len += code.len();
+        self
+    }
+
+    /// Appends text that is displayed as is, with an optional highlight.
+    pub fn literal(mut self, text: &str, highlight: Option<&str>) -> Self {
+        if text.is_empty() {
+            return self;
+        }
+        self.spans
+            .push(CodeLabelSpan::literal(text, highlight.map(str::to_string)));
+        self.display_len += text.len();
+        self
+    }
+
+    /// Appends the spans added by `build`, filtering completions by their displayed text.
+    pub fn filtered(self, build: impl FnOnce(Self) -> Self) -> Self {
+        let start = self.display_len;
+        let mut builder = build(self);
+        builder.filter_range = Some(start..builder.display_len);
+        builder
+    }
+
+    pub fn build(self) -> CodeLabel {
+        CodeLabel {
+            code: self.code,
+            spans: self.spans,
+            filter_range: self.filter_range.unwrap_or(0..self.display_len).into(),
+        }
+    }
+}
+
+/// Renders a completion the way Elixir code reads, for the kinds all servers report alike.
+pub(super) fn completion(completion: &CompletionItem) -> Option<CodeLabel> {
+    match completion.kind? {
+        CompletionKind::Module | CompletionKind::Class | CompletionKind::Interface => {
+            Some(module(completion.label, completion.detail))
+        }
+        CompletionKind::Struct => Some(struct_literal(completion.label, completion.detail)),
+        CompletionKind::Function | CompletionKind::Constant => {
+            Some(function_completion(completion))
+        }
+        CompletionKind::Operator => Some(operator_completion(completion)),
+        CompletionKind::Variable => Some(variable_completion(completion)),
+        CompletionKind::Field | CompletionKind::Property | CompletionKind::EnumMember => {
+            Some(field_completion(completion))
+        }
+        CompletionKind::Keyword | CompletionKind::Snippet => Some(keyword_completion(completion)),
+        _ => None,
+    }
+}
+
+/// Renders a document or workspace symbol the way it is defined in Elixir code.
+pub(super) fn symbol(symbol: &Symbol) -> Option<CodeLabel> {
+    let name = &symbol.name;
+    match symbol.kind {
+        SymbolKind::TypeParameter => Some(attribute_symbol(name)),
+        SymbolKind::Interface | SymbolKind::Property | SymbolKind::Constant
+            if name.starts_with('@') =>
+        {
+            Some(attribute_symbol(name))
+        }
+        SymbolKind::Module | SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct => {
+            Some(module(name, None))
+        }
+        SymbolKind::Method | SymbolKind::Event | SymbolKind::Key => test_symbol(name),
+        SymbolKind::Function | SymbolKind::Constant => Some(function_symbol(name)),
+        SymbolKind::Property | SymbolKind::Field => Some(field(name, None)),
+        _ => None,
+    }
+}
+
+/// Renders a function completion with its signature from the label or `label_details.detail`,
+/// and its module from `label_details.description` or `detail`.
+fn function_completion(completion: &CompletionItem) -> CodeLabel {
+    let (name, rest) = split_signature(completion.label);
+    let signature = match completion.label_detail {
+        Some(detail) if !rest.starts_with('(') => detail,
+        _ => rest,
+    };
+
+    Function {
+        name,
+        arity: arity(rest).or_else(|| arity(signature)),
+        // The arity already covers signatures without arguments.
+        signature: match signature.trim_start_matches(|c: char| c == '/' || c.is_ascii_digit()) {
+            "()" => "",
+            signature => signature,
+        },
+        origin: completion_origin(completion),
+        is_macro: is_macro_completion(completion),
+        is_deprecated: is_deprecated_completion(completion),
+    }
+    .build()
+}
+
+fn operator_completion(completion: &CompletionItem) -> CodeLabel {
+    operator(completion.label, completion_origin(completion))
+}
+
+/// Renders module attributes as `@name` and other variables as identifiers.
+fn variable_completion(completion: &CompletionItem) -> CodeLabel {
+    let detail = completion.detail;
+    if completion.label.starts_with('@') {
+        attribute(completion.label, detail)
+    } else {
+        variable(completion.label, detail)
+    }
+}
+
+/// Renders struct fields and map keys as `name:`, and module attributes as `@name`.
+fn field_completion(completion: &CompletionItem) -> CodeLabel {
+    let detail = completion.detail;
+    if completion.label.starts_with('@') {
+        attribute(completion.label, detail)
+    } else {
+        field(completion.label.trim_end_matches(':'), detail)
+    }
+}
+
+/// Renders keywords and snippets with the keyword highlighted, followed by a dimmed preview of
+/// the snippet body.
+fn keyword_completion(completion: &CompletionItem) -> CodeLabel {
+    // Snippet previews usually start with the keyword itself, which is already the label.
+    let body = completion
+        .label_detail
+        .or(completion.detail)
+        .map(snippet_preview)
+        .map(|preview| match preview.strip_prefix(completion.label) {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+                rest.trim_start().to_string()
+            }
+            _ => preview,
+        })
+        .filter(|body| !body.is_empty());
+
+    let label =
+        LabelBuilder::new().filtered(|label| label.literal(completion.label, Some("keyword")));
+    match body {
+        Some(body) => label
+            .literal(&format!(" {body}"), Some("comment.unused"))
+            .build(),
+        None => label.build(),
+    }
+}
+
+/// Renders a function symbol, whose name may start with the `def`/`defmacro` that defines it.
+fn function_symbol(name: &str) -> CodeLabel {
+    // Some servers report ExUnit tests as functions.
+    if let Some(label) = test_symbol(name) {
+        return label;
+    }
+
+    let (name, is_macro) = match name.split_once(' ') {
+        Some(("defmacro" | "defmacrop", name)) => (name, true),
+        Some(("def" | "defp", name)) => (name, false),
+        _ => (name, false),
+    };
+    let (name, signature) = split_signature(name);
+
+    Function {
+        name,
+        signature,
+        is_macro,
+        ..Default::default()
+    }
+    .build()
+}
+
+/// Renders ExUnit `test "name"`, `describe "name"` and `setup` symbols with the keyword
+/// highlighted, returning `None` for symbols that aren't part of a test suite.
+fn test_symbol(name: &str) -> Option<CodeLabel> {
+    let (keyword, description) = name.split_once(' ').unwrap_or((name, ""));
+    if !TEST_KEYWORDS.contains(&keyword) {
+        return None;
+    }
+
+    let label = LabelBuilder::new().literal(keyword, Some("keyword"));
+    let description = description.trim();
+    let label = if description.is_empty() {
+        label
+    } else if keyword.starts_with("setup") || description.starts_with('"') {
+        label.literal(" ", None).code(description)
+    } else {
+        label.literal(" ", None).code(&format!("\"{description}\""))
+    };
+
+    Some(label.build())
+}
+
+/// Renders module attribute symbols such as `@type t :: term`, `@callback init(args)` or
+/// `@behaviour GenServer`. Names without an attribute are treated as types.
+fn attribute_symbol(name: &str) -> CodeLabel {
+    let name = if name.starts_with('@') {
+        name.to_string()
+    } else {
+        format!("@type {name}")
+    };
+    let (attribute, definition) = name.split_once(' ').unwrap_or((&name, ""));
+
+    let mut label = LabelBuilder::new().code(attribute);
+    if !definition.is_empty() {
+        let (definition_name, rest) =
+            definition.split_at(definition.find(['(', ' ']).unwrap_or(definition.len()));
+        label = label
+            .code(" ")
+            .filtered(|label| label.code(definition_name))
+            .code(rest);
+    }
+    // Typespecs only parse, and so highlight, as such when they have a `::`.
+    if TYPESPEC_ATTRIBUTES.contains(&attribute) && !definition.contains("::") {
+        label = label.hidden(" :: term");
+    }
+
+    label.build()
+}
+
+pub(super) fn module(name: &str, detail: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new()
+        .hidden("defmodule ")
+        .filtered(|label| label.code(name));
+
+    with_detail(label, detail).build()
+}
+
+/// Renders a struct the way it is built, like `%User{}`.
+fn struct_literal(name: &str, detail: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new()
+        .code("%")
+        .filtered(|label| label.code(name))
+        .code("{}");
+
+    with_detail(label, detail).build()
+}
+
+/// A function or macro, rendered as `name/arity (args)` followed by the module it comes from.
+#[derive(Default)]
+struct Function<'a> {
+    name: &'a str,
+    /// When known, the arity is filtered on along with the name, so `map/3` narrows completions
+    /// to the right function.
+    arity: Option<usize>,
+    signature: &'a str,
+    origin: Option<&'a str>,
+    is_macro: bool,
+    is_deprecated: bool,
+}
+
+impl Function<'_> {
+    /// Macros are prefixed with a visible `defmacro`, so they stand out from functions, and
+    /// deprecated functions are dimmed rather than highlighted.
+    fn build(&self) -> CodeLabel {
+        let dimmed = Some("comment.unused");
+        let arity = self
+            .arity
+            .map(|arity| format!("/{arity}"))
+            .unwrap_or_default();
+        let separator = if arity.is_empty() || self.signature.is_empty() {
+            ""
+        } else {
+            " "
+        };
+
+        let label = match (self.is_macro, self.is_deprecated) {
+            (true, false) => LabelBuilder::new().code("defmacro "),
+            (true, true) => LabelBuilder::new().literal("defmacro ", dimmed),
+            (false, false) => LabelBuilder::new().hidden("def "),
+            (false, true) => LabelBuilder::new(),
+        };
+
+        let label = if self.is_deprecated {
+            let label = label
+                .filtered(|label| label.literal(self.name, dimmed).literal(&arity, dimmed))
+                .literal(separator, None)
+                .literal(self.signature, dimmed);
+            // Servers that flag deprecation in the module description already show it there.
+            if self
+                .origin
+                .is_some_and(|origin| origin.to_ascii_lowercase().contains("deprecated"))
+            {
+                label
+            } else {
+                label.literal(" (deprecated)", Some("comment"))
+            }
+        } else {
+            let label = label.filtered(|label| label.code(self.name).literal(&arity, None));
+            // Only parenthesized arguments parse as part of the definition.
+            if self.signature.starts_with('(') {
+                label.literal(separator, None).code(self.signature)
+            } else {
+                label.literal(self.signature, None)
+            }
+        };
+
+        with_origin(label, self.origin).build()
+    }
+}
+
+/// Renders an operator in infix position, like `a |> b`.
+fn operator(name: &str, origin: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new()
+        .hidden("def a ")
+        .filtered(|label| label.code(name))
+        .hidden(" b");
+
+    with_origin(label, origin).build()
+}
+
+fn variable(name: &str, detail: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new().filtered(|label| label.literal(name, Some("variable")));
+
+    with_detail(label, detail).build()
+}
+
+fn attribute(name: &str, detail: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new().filtered(|label| label.code(name));
+
+    with_detail(label, detail).build()
+}
+
+/// Renders a key the way it appears in a struct or keyword list, like `name:`.
+fn field(name: &str, detail: Option<&str>) -> CodeLabel {
+    let label = LabelBuilder::new()
+        .hidden("%{")
+        .filtered(|label| label.code(name))
+        .code(":")
+        .hidden(" nil}");
+
+    // The colon already separates the key from its type.
+    with_origin(label, detail).build()
+}
+
+/// Splits a completion label such as `map(enumerable, fun)` or `map/2` into its name and the
+/// signature or arity that follows it.
+fn split_signature(label: &str) -> (&str, &str) {
+    match label.find(['(', '/']) {
+        Some(index) if index > 0 => label.split_at(index),
+        _ => (label, ""),
+    }
+}
+
+/// Returns the arity a signature such as `/2` or `(enumerable, fun)` spells out.
+fn arity(signature: &str) -> Option<usize> {
+    if let Some(arity) = signature.strip_prefix('/') {
+        return arity.parse().ok();
+    }
+
+    let arguments = signature.strip_prefix('(')?;
+    let mut depth = 0;
+    let mut commas = 0;
+    let mut has_arguments = false;
+    for character in arguments.chars() {
+        match character {
+            ')' | ']' | '}' if depth == 0 => {
+                return Some(if has_arguments { commas + 1 } else { 0 });
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => commas += 1,
+            _ => {}
+        }
+        if !character.is_whitespace() {
+            has_arguments = true;
+        }
+    }
+
+    None
+}
+
+const TEST_KEYWORDS: &[&str] = &["describe", "test", "property", "setup", "setup_all"];
+
+const TYPESPEC_ATTRIBUTES: &[&str] = &[
+    "@type",
+    "@typep",
+    "@opaque",
+    "@spec",
+    "@callback",
+    "@macrocallback",
+];
+
+const SNIPPET_PREVIEW_LEN: usize = 40;
+
+/// Turns a snippet such as `defmodule ${1:Name} do\n  $0\nend` into a one-line preview like
+/// `defmodule Name do end`, dropping tab stops and keeping placeholder text.
+fn snippet_preview(snippet: &str) -> String {
+    let mut preview = String::new();
+    let mut open_placeholders = 0;
+    let mut chars = snippet.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => preview.extend(chars.next()),
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                chars.next_if_eq(&':');
+                open_placeholders += 1;
+            }
+            '$' => while chars.next_if(char::is_ascii_digit).is_some() {},
+            '}' if open_placeholders > 0 => open_placeholders -= 1,
+            character => preview.push(character),
+        }
+    }
+
+    let preview = preview.split_whitespace().collect::<Vec<_>>().join(" ");
+    match preview.char_indices().nth(SNIPPET_PREVIEW_LEN) {
+        Some((index, _)) => format!("{}…", &preview[..index]),
+        None => preview,
+    }
+}
+
+/// Whether the server describes the completion as a macro, like ElixirLS's `macro` description
+/// or a `defmacro` detail.
+fn is_macro_completion(completion: &CompletionItem) -> bool {
+    [
+        completion.label_description,
+        completion.label_detail,
+        completion.detail,
+    ]
+    .into_iter()
+    .flatten()
+    .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+    .any(|word| matches!(word, "macro" | "defmacro" | "defmacrop"))
+}
+
+/// Whether the server marks the completion as deprecated in its details.
+///
+/// The extension API doesn't expose the completion's `tags`, `deprecated` flag or
+/// `documentation`, so this is the only deprecation information available to labels.
+fn is_deprecated_completion(completion: &CompletionItem) -> bool {
+    [completion.label_description, completion.detail]
+        .into_iter()
+        .flatten()
+        .any(|text| text.to_ascii_lowercase().contains("deprecated"))
+}
+
+fn completion_origin<'a>(completion: &CompletionItem<'a>) -> Option<&'a str> {
+    completion.label_description.or(completion.detail)
+}
+
+fn with_origin(label: LabelBuilder, origin: Option<&str>) -> LabelBuilder {
+    match origin {
+        Some(origin) if !origin.is_empty() => {
+            label.literal(&format!(" {origin}"), Some("comment.unused"))
+        }
+        _ => label,
+    }
+}
+
+fn with_detail(label: LabelBuilder, detail: Option<&str>) -> LabelBuilder {
+    match detail {
+        Some(detail) if !detail.is_empty() => {
+            label.literal(&format!(": {detail}"), Some("comment.unused"))
+        }
+        _ => label,
+    }
+}
+
+#[cfg(test)]
+mod tests;
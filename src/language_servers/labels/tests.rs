@@ -0,0 +1,346 @@
+//! Golden tests for the labels the language servers render.
+//!
+//! The labels rendered by the shared renderer are compared against `golden/labels.txt`, and the
+//! module labels ElixirLS renders itself against `golden/elixir-ls.txt`. Every other label must
+//! match the shared renderer. Run the tests with `UPDATE_GOLDEN=1` to rewrite the golden files
+//! after an intended change.
+
+use std::{env, fmt::Write, fs};
+
+use zed_extension_api::{
+    CodeLabel, CodeLabelSpan,
+    lsp::{CompletionKind, Symbol, SymbolKind},
+};
+
+use crate::language_servers::{
+    ElixirLs, LANGUAGE_SERVERS, LanguageServer,
+    labels::{self, CompletionItem},
+};
+
+fn completions() -> Vec<CompletionItem<'static>> {
+    vec![
+        CompletionItem {
+            label: "Enum",
+            kind: Some(CompletionKind::Module),
+            detail: Some("module"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "Repo",
+            kind: Some(CompletionKind::Module),
+            detail: Some("alias"),
+            label_description: Some("alias MyApp.Repo"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "GenServer",
+            kind: Some(CompletionKind::Interface),
+            detail: Some("behaviour"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "User",
+            kind: Some(CompletionKind::Struct),
+            detail: Some("struct"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "map",
+            kind: Some(CompletionKind::Function),
+            detail: Some("(function)"),
+            label_detail: Some("(enumerable, fun)"),
+            label_description: Some("Enum"),
+        },
+        CompletionItem {
+            label: "map/2",
+            kind: Some(CompletionKind::Function),
+            detail: Some("Stream.map/2"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "reduce(enumerable, acc, fun)",
+            kind: Some(CompletionKind::Function),
+            label_description: Some("Enum"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "utc_now",
+            kind: Some(CompletionKind::Function),
+            label_detail: Some("()"),
+            label_description: Some("DateTime"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "new",
+            kind: Some(CompletionKind::Function),
+            label_detail: Some("(fields \\\\ %{name: nil, email: nil})"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "pi",
+            kind: Some(CompletionKind::Constant),
+            label_description: Some(":math"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "if",
+            kind: Some(CompletionKind::Function),
+            label_detail: Some("(condition, clauses)"),
+            label_description: Some("Kernel (macro)"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "chunk",
+            kind: Some(CompletionKind::Function),
+            label_detail: Some("(enumerable, count)"),
+            label_description: Some("Enum (deprecated)"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "filter_map",
+            kind: Some(CompletionKind::Function),
+            detail: Some("@deprecated Use Enum.filter/2 + Enum.map/2 instead"),
+            label_detail: Some("(enumerable, filter, mapper)"),
+            label_description: Some("Enum"),
+        },
+        CompletionItem {
+            label: "|>",
+            kind: Some(CompletionKind::Operator),
+            detail: Some("Kernel"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "user",
+            kind: Some(CompletionKind::Variable),
+            detail: Some("%User{}"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "@moduledoc",
+            kind: Some(CompletionKind::Variable),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "name",
+            kind: Some(CompletionKind::Field),
+            detail: Some("String.t()"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "email:",
+            kind: Some(CompletionKind::Property),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "@timeout",
+            kind: Some(CompletionKind::Property),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "ok",
+            kind: Some(CompletionKind::EnumMember),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "defmodule",
+            kind: Some(CompletionKind::Snippet),
+            detail: Some("defmodule ${1:Name} do\n  $0\nend"),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "with",
+            kind: Some(CompletionKind::Keyword),
+            ..Default::default()
+        },
+        CompletionItem {
+            label: "lib",
+            kind: Some(CompletionKind::Folder),
+            ..Default::default()
+        },
+    ]
+}
+
+fn symbols() -> Vec<Symbol> {
+    [
+        (SymbolKind::Module, "MyApp.Accounts"),
+        (SymbolKind::Struct, "MyApp.Accounts.User"),
+        (SymbolKind::Function, "create_user(attrs)"),
+        (SymbolKind::Function, "def create_user(attrs)"),
+        (SymbolKind::Function, "defmacro schema(source, do: block)"),
+        (SymbolKind::Function, "MyApp.Accounts.get_user/1"),
+        (SymbolKind::Constant, "default_timeout"),
+        (SymbolKind::Method, "test \"creates user\""),
+        (SymbolKind::Event, "describe \"create_user/1\""),
+        (SymbolKind::Key, "setup :create_user"),
+        (SymbolKind::Function, "test creates user"),
+        (SymbolKind::TypeParameter, "t"),
+        (SymbolKind::TypeParameter, "@type t :: %__MODULE__{}"),
+        (
+            SymbolKind::Property,
+            "@spec create_user(map()) :: {:ok, User.t()}",
+        ),
+        (SymbolKind::Interface, "@callback init(args)"),
+        (SymbolKind::Interface, "@behaviour GenServer"),
+        (SymbolKind::Constant, "@timeout"),
+        (SymbolKind::Property, "email"),
+        (SymbolKind::Variable, "user"),
+    ]
+    .into_iter()
+    .map(|(kind, name)| Symbol {
+        kind,
+        name: name.to_string(),
+    })
+    .collect()
+}
+
+#[test]
+fn labels_match_golden_file() {
+    let mut actual = String::new();
+
+    for completion in completions() {
+        write_completion(&mut actual, &completion, labels::completion(&completion));
+    }
+    for symbol in symbols() {
+        write_symbol(&mut actual, &symbol, labels::symbol(&symbol));
+    }
+
+    assert_golden("labels", &actual);
+}
+
+#[test]
+fn elixir_ls_module_labels_match_golden_file() {
+    let elixir_ls = ElixirLs::new();
+    let mut actual = String::new();
+
+    for completion in completions()
+        .iter()
+        .filter(|completion| is_module(completion))
+    {
+        write_completion(
+            &mut actual,
+            completion,
+            elixir_ls.label_for_completion(completion),
+        );
+    }
+
+    assert_golden(ElixirLs::LANGUAGE_SERVER_ID, &actual);
+}
+
+#[test]
+fn language_servers_use_the_shared_labels() {
+    for (language_server_id, new_language_server) in LANGUAGE_SERVERS {
+        let language_server = new_language_server();
+
+        for completion in completions() {
+            if *language_server_id == ElixirLs::LANGUAGE_SERVER_ID && is_module(&completion) {
+                continue;
+            }
+
+            let (mut actual, mut expected) = (String::new(), String::new());
+            render(
+                &mut actual,
+                language_server.label_for_completion(&completion),
+            );
+            render(&mut expected, labels::completion(&completion));
+            assert_eq!(
+                actual, expected,
+                "{language_server_id}: {}",
+                completion.label
+            );
+        }
+
+        for symbol in symbols() {
+            let name = symbol.name.clone();
+            let (mut actual, mut expected) = (String::new(), String::new());
+            render(&mut expected, labels::symbol(&symbol));
+            render(&mut actual, language_server.label_for_symbol(symbol));
+            assert_eq!(actual, expected, "{language_server_id}: {name}");
+        }
+    }
+}
+
+/// The completions ElixirLS labels itself, see its `label_for_completion`.
+fn is_module(completion: &CompletionItem) -> bool {
+    matches!(
+        completion.kind,
+        Some(CompletionKind::Module | CompletionKind::Class)
+    )
+}
+
+fn write_completion(output: &mut String, completion: &CompletionItem, label: Option<CodeLabel>) {
+    writeln!(
+        output,
+        "completion {:?} {:?} detail={:?} label_detail={:?} label_description={:?}",
+        completion.kind,
+        completion.label,
+        completion.detail,
+        completion.label_detail,
+        completion.label_description,
+    )
+    .unwrap();
+    render(output, label);
+}
+
+fn write_symbol(output: &mut String, symbol: &Symbol, label: Option<CodeLabel>) {
+    writeln!(output, "symbol {:?} {:?}", symbol.kind, symbol.name).unwrap();
+    render(output, label);
+}
+
+/// Writes the label's code, spans and filter range, along with the text they display.
+fn render(output: &mut String, label: Option<CodeLabel>) {
+    let Some(label) = label else {
+        writeln!(output, "  none\n").unwrap();
+        return;
+    };
+
+    let mut display = String::new();
+    writeln!(output, "  code: {:?}", label.code).unwrap();
+    for span in &label.spans {
+        match span {
+            CodeLabelSpan::CodeRange(range) => {
+                let text = &label.code[range.start as usize..range.end as usize];
+                display.push_str(text);
+                writeln!(output, "  code {}..{} {text:?}", range.start, range.end).unwrap();
+            }
+            CodeLabelSpan::Literal(literal) => {
+                display.push_str(&literal.text);
+                writeln!(
+                    output,
+                    "  literal {:?} {}",
+                    literal.text,
+                    literal.highlight_name.as_deref().unwrap_or("-")
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    let filter_range = label.filter_range.start as usize..label.filter_range.end as usize;
+    writeln!(output, "  display: {display:?}").unwrap();
+    writeln!(
+        output,
+        "  filter: {}..{} {:?}\n",
+        filter_range.start,
+        filter_range.end,
+        &display[filter_range.clone()]
+    )
+    .unwrap();
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let path = format!(
+        "{}/src/language_servers/labels/golden/{name}.txt",
+        env!("CARGO_MANIFEST_DIR")
+    );
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "labels for {name} differ from {path}, rerun with UPDATE_GOLDEN=1 to update it:\n{actual}"
+    );
+}
@@ -1,17 +1,14 @@
-use std::fs;
-
 use zed_extension_api::{
-    self as zed, CodeLabel, CodeLabelSpan, LanguageServerId, Result, Worktree,
-    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
+    self as zed, LanguageServerId, Result, Worktree,
     serde_json::{Value, json},
 };
 
-use crate::language_servers::{config, util};
-
-struct NextLsBinary {
-    path: String,
-    args: Vec<String>,
-}
+use crate::language_servers::{
+    LanguageServer,
+    binary::{BinaryLookup, LanguageServerBinary},
+    config,
+    installer::{GithubReleaseInstaller, ReleaseChannel},
+};
 
 pub struct NextLs {
     cached_binary_path: Option<String>,
@@ -26,156 +23,91 @@ impl NextLs {
         }
     }
 
-    pub fn language_server_command(
+    fn language_server_binary(
         &mut self,
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
-    ) -> Result<zed::Command> {
-        let next_ls = self.language_server_binary(language_server_id, worktree)?;
+    ) -> Result<LanguageServerBinary> {
+        BinaryLookup {
+            language_server_id: Self::LANGUAGE_SERVER_ID,
+            binary_name: &Self::LANGUAGE_SERVER_ID.replace("-", ""),
+            default_args: &["--stdio"],
+        }
+        .find_or_install(
+            worktree,
+            &mut self.cached_binary_path,
+            || Self::installer(worktree),
+            |installer| installer.install(Some(language_server_id)),
+        )
+    }
 
-        Ok(zed::Command {
-            command: next_ls.path,
-            args: next_ls.args,
-            env: Default::default(),
+    fn installer(worktree: &Worktree) -> Result<GithubReleaseInstaller> {
+        let (platform, arch) = zed::current_platform();
+
+        Ok(GithubReleaseInstaller {
+            repo: "elixir-tools/next-ls",
+            asset_name: format!(
+                "{}_{os}_{arch}{extension}",
+                Self::LANGUAGE_SERVER_ID.replace("-", "_"),
+                os = match platform {
+                    zed::Os::Mac => "darwin",
+                    zed::Os::Linux => "linux",
+                    zed::Os::Windows => "windows",
+                },
+                arch = match arch {
+                    zed::Architecture::Aarch64 => "arm64",
+                    zed::Architecture::X8664 => "amd64",
+                    zed::Architecture::X86 =>
+                        return Err(format!("unsupported architecture: {arch:?}")),
+                },
+                extension = match platform {
+                    zed::Os::Mac | zed::Os::Linux => "",
+                    zed::Os::Windows => ".exe",
+                },
+            ),
+            file_type: zed::DownloadedFileType::Uncompressed,
+            binary_name: Self::LANGUAGE_SERVER_ID.to_string(),
+            executables: vec![],
+            ..GithubReleaseInstaller::new(Self::LANGUAGE_SERVER_ID, worktree, ReleaseChannel::ALL)?
         })
     }
+}
 
-    fn language_server_binary(
+impl LanguageServer for NextLs {
+    fn language_server_command(
         &mut self,
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
-    ) -> Result<NextLsBinary> {
-        let (platform, arch) = zed::current_platform();
-
-        let binary_settings = config::get_binary_settings(Self::LANGUAGE_SERVER_ID, worktree);
-        let binary_args = config::get_binary_args(&binary_settings)
-            .unwrap_or_else(|| vec!["--stdio".to_string()]);
-
-        if let Some(binary_path) = config::get_binary_path(&binary_settings) {
-            return Ok(NextLsBinary {
-                path: binary_path,
-                args: binary_args,
-            });
-        }
-
-        if let Some(binary_path) = worktree.which(&Self::LANGUAGE_SERVER_ID.replace("-", "")) {
-            return Ok(NextLsBinary {
-                path: binary_path,
-                args: binary_args,
-            });
-        }
-
-        if let Some(binary_path) = &self.cached_binary_path
-            && fs::metadata(binary_path).is_ok_and(|stat| stat.is_file())
-        {
-            return Ok(NextLsBinary {
-                path: binary_path.clone(),
-                args: binary_args,
-            });
-        }
-
-        zed::set_language_server_installation_status(
-            language_server_id,
-            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
-        );
-
-        let release = match zed::latest_github_release(
-            "elixir-tools/next-ls",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        ) {
-            Ok(release) => release,
-            Err(_) => {
-                if let Some(binary_path) =
-                    util::find_existing_binary(Self::LANGUAGE_SERVER_ID, Self::LANGUAGE_SERVER_ID)
-                {
-                    self.cached_binary_path = Some(binary_path.clone());
-                    return Ok(NextLsBinary {
-                        path: binary_path,
-                        args: binary_args,
-                    });
-                }
-                return Err("failed to download latest github release".to_string());
-            }
-        };
-
-        let asset_name = format!(
-            "{}_{os}_{arch}{extension}",
-            Self::LANGUAGE_SERVER_ID.replace("-", "_"),
-            os = match platform {
-                zed::Os::Mac => "darwin",
-                zed::Os::Linux => "linux",
-                zed::Os::Windows => "windows",
-            },
-            arch = match arch {
-                zed::Architecture::Aarch64 => "arm64",
-                zed::Architecture::X8664 => "amd64",
-                zed::Architecture::X86 =>
-                    return Err(format!("unsupported architecture: {arch:?}")),
-            },
-            extension = match platform {
-                zed::Os::Mac | zed::Os::Linux => "",
-                zed::Os::Windows => ".exe",
-            },
-        );
-
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("{}-{}", Self::LANGUAGE_SERVER_ID, release.version);
-        fs::create_dir_all(&version_dir).map_err(|e| format!("failed to create directory: {e}"))?;
-
-        let binary_path = format!("{}/{}", version_dir, Self::LANGUAGE_SERVER_ID);
-
-        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::Downloading,
-            );
-
-            zed::download_file(
-                &asset.download_url,
-                &binary_path,
-                zed::DownloadedFileType::Uncompressed,
-            )
-            .map_err(|e| format!("failed to download file: {e}"))?;
-
-            zed::make_file_executable(&binary_path)?;
-
-            util::remove_outdated_versions(Self::LANGUAGE_SERVER_ID, &version_dir)?;
-        }
+    ) -> Result<zed::Command> {
+        let next_ls = self.language_server_binary(language_server_id, worktree)?;
 
-        self.cached_binary_path = Some(binary_path.clone());
-        Ok(NextLsBinary {
-            path: binary_path,
-            args: binary_args,
+        Ok(zed::Command {
+            command: next_ls.path,
+            args: next_ls.args,
+            env: config::get_command_env(Self::LANGUAGE_SERVER_ID, worktree),
         })
     }
 
-    pub fn language_server_initialization_options(
+    fn language_server_initialization_options(
         &mut self,
         worktree: &Worktree,
     ) -> Result<Option<Value>> {
-        let settings = config::get_initialization_options(Self::LANGUAGE_SERVER_ID, worktree)
-            .unwrap_or_else(|| {
-                json!({
-                    "experimental": {
-                        "completions": {
-                            "enable": true
-                        }
+        let settings = config::get_initialization_options(
+            Self::LANGUAGE_SERVER_ID,
+            worktree,
+            json!({
+                "experimental": {
+                    "completions": {
+                        "enable": true
                     }
-                })
-            });
+                }
+            }),
+        );
 
         Ok(Some(settings))
     }
 
-    pub fn language_server_workspace_configuration(
+    fn language_server_workspace_configuration(
         &mut self,
         worktree: &Worktree,
     ) -> Result<Option<Value>> {
@@ -184,84 +116,4 @@ impl NextLs {
 
         Ok(Some(settings))
     }
-
-    pub fn label_for_completion(&self, completion: Completion) -> Option<CodeLabel> {
-        match completion.kind? {
-            CompletionKind::Module
-            | CompletionKind::Class
-            | CompletionKind::Interface
-            | CompletionKind::Struct => {
-                let name = completion.label;
-                let defmodule = "defmodule ";
-                let code = format!("{defmodule}{name}");
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![CodeLabelSpan::code_range(
-                        defmodule.len()..defmodule.len() + name.len(),
-                    )],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            CompletionKind::Function | CompletionKind::Constant => {
-                let name = completion.label;
-                let def = "def ";
-                let code = format!("{def}{name}");
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![CodeLabelSpan::code_range(def.len()..def.len() + name.len())],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            CompletionKind::Operator => {
-                let name = completion.label;
-                let def_a = "def a ";
-                let code = format!("{def_a}{name} b");
-
-                Some(CodeLabel {
-                    code,
-                    spans: vec![CodeLabelSpan::code_range(
-                        def_a.len()..def_a.len() + name.len(),
-                    )],
-                    filter_range: (0..name.len()).into(),
-                })
-            }
-            _ => None,
-        }
-    }
-
-    pub fn label_for_symbol(&self, symbol: Symbol) -> Option<CodeLabel> {
-        let name = &symbol.name;
-
-        let (code, filter_range, display_range) = match symbol.kind {
-            SymbolKind::Module | SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct => {
-                let defmodule = "defmodule ";
-                let code = format!("{defmodule}{name}");
-                let filter_range = 0..name.len();
-                let display_range = defmodule.len()..defmodule.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            SymbolKind::Function | SymbolKind::Constant => {
-                let def = "def ";
-                let code = format!("{def}{name}");
-                let filter_range = 0..name.len();
-                let display_range = def.len()..def.len() + name.len();
-                (code, filter_range, display_range)
-            }
-            SymbolKind::Property => {
-                let code = name.clone();
-                let filter_range = 0..code.len();
-                let display_range = 0..code.len();
-                (code, filter_range, display_range)
-            }
-            _ => return None,
-        };
-
-        Some(CodeLabel {
-            spans: vec![CodeLabelSpan::code_range(display_range)],
-            filter_range: filter_range.into(),
-            code,
-        })
-    }
 }
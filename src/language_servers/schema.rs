@@ -0,0 +1,169 @@
+use std::sync::OnceLock;
+
+use zed_extension_api::serde_json::{self, Map, Value};
+
+use crate::language_servers::{Dexter, ElixirLs, Expert, Lexical, NextLs};
+
+/// The schemas in `language_server_schemas`, describing the `settings` and
+/// `initialization_options` of each language server's `lsp.<id>` settings.
+const SCHEMAS: &[(&str, &str)] = &[
+    (
+        Expert::LANGUAGE_SERVER_ID,
+        include_str!("../../language_server_schemas/expert.json"),
+    ),
+    (
+        ElixirLs::LANGUAGE_SERVER_ID,
+        include_str!("../../language_server_schemas/elixir-ls.json"),
+    ),
+    (
+        Dexter::LANGUAGE_SERVER_ID,
+        include_str!("../../language_server_schemas/dexter.json"),
+    ),
+    (
+        NextLs::LANGUAGE_SERVER_ID,
+        include_str!("../../language_server_schemas/next-ls.json"),
+    ),
+    (
+        Lexical::LANGUAGE_SERVER_ID,
+        include_str!("../../language_server_schemas/lexical.json"),
+    ),
+];
+
+/// Language servers whose schema lists every setting the server reads, so that a problem with
+/// their settings is known to be a mistake. Other schemas only describe the common settings.
+const COMPLETE_SCHEMAS: &[&str] = &[NextLs::LANGUAGE_SERVER_ID];
+
+pub(super) fn is_complete(language_server_id: &str) -> bool {
+    COMPLETE_SCHEMAS.contains(&language_server_id)
+}
+
+/// Validates a section of a language server's settings against its schema, returning a message
+/// for every unknown key or mistyped value.
+///
+/// Only the subset of JSON Schema used by the bundled schemas is supported: `type`, `enum`,
+/// `minimum`, `properties`, `additionalProperties` and `items`.
+pub(super) fn validate(language_server_id: &str, section: &str, value: &Value) -> Vec<String> {
+    let Some(schema) = parsed_schemas()
+        .iter()
+        .find_map(|(id, schema)| (*id == language_server_id).then_some(schema))
+    else {
+        return Vec::new();
+    };
+    let Some(section_schema) = schema.pointer(&format!("/properties/{section}")) else {
+        return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    validate_value(section_schema, value, section, &mut problems);
+    problems
+}
+
+/// Parses the bundled schemas once, as settings are validated whenever a server requests them.
+fn parsed_schemas() -> &'static [(&'static str, Value)] {
+    static PARSED_SCHEMAS: OnceLock<Vec<(&str, Value)>> = OnceLock::new();
+
+    PARSED_SCHEMAS.get_or_init(|| {
+        SCHEMAS
+            .iter()
+            .filter_map(|(id, schema)| Some((*id, serde_json::from_str(schema).ok()?)))
+            .collect()
+    })
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str, problems: &mut Vec<String>) {
+    // `null` removes a value, for example to opt out of a default initialization option.
+    if value.is_null() {
+        return;
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types = match expected {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            expected => expected.as_str().into_iter().collect::<Vec<_>>(),
+        };
+        if !types.iter().any(|expected| has_type(value, expected)) {
+            problems.push(format!(
+                "`{path}` should be {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        let allowed = allowed
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        problems.push(format!("`{path}` should be one of {allowed}, got {value}"));
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) && number < minimum
+    {
+        problems.push(format!(
+            "`{path}` should be at least {minimum}, got {value}"
+        ));
+    }
+
+    match value {
+        Value::Object(object) => validate_object(schema, object, path, problems),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{path}[{index}]"), problems);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_object(
+    schema: &Value,
+    object: &Map<String, Value>,
+    path: &str,
+    problems: &mut Vec<String>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional_properties = schema.get("additionalProperties");
+
+    for (key, value) in object {
+        let key_path = format!("{path}.{key}");
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property_schema) => validate_value(property_schema, value, &key_path, problems),
+            None => match additional_properties {
+                Some(Value::Bool(false)) => problems.push(format!("unknown setting `{key_path}`")),
+                Some(property_schema @ Value::Object(_)) => {
+                    validate_value(property_schema, value, &key_path, problems)
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        expected => type_name(value) == expected,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
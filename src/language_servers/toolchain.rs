@@ -0,0 +1,158 @@
+use zed_extension_api::{Worktree, process::Command};
+
+use crate::language_servers::{version::Version, version_managers};
+
+/// The Elixir and Erlang/OTP versions a project is built with.
+#[derive(Debug, Default)]
+pub(super) struct Toolchain {
+    pub elixir: Option<Version>,
+    pub otp: Option<u64>,
+    /// The newest Elixir version the `elixir:` requirement in `mix.exs` allows, when it caps it.
+    pub newest_allowed_elixir: Option<Version>,
+}
+
+impl Toolchain {
+    /// Detects the project's toolchain from `.tool-versions`/mise configs and then from the
+    /// installed `elixir` executable.
+    ///
+    /// The `elixir:` requirement in `mix.exs` only bounds the versions a project may run on, so it
+    /// is kept apart from the detected versions.
+    pub fn detect(worktree: &Worktree) -> Self {
+        let mut toolchain = Self::from_version_managers(worktree);
+
+        if toolchain.elixir.is_none() || toolchain.otp.is_none() {
+            let installed = Self::from_elixir_executable(worktree);
+            toolchain.elixir = toolchain.elixir.or(installed.elixir);
+            toolchain.otp = toolchain.otp.or(installed.otp);
+        }
+
+        toolchain.newest_allowed_elixir = worktree
+            .read_text_file("mix.exs")
+            .ok()
+            .and_then(|mix_exs| mix_newest_allowed_elixir(&mix_exs));
+
+        toolchain
+    }
+
+    fn from_version_managers(worktree: &Worktree) -> Self {
+        // Elixir versions such as `1.17.3-otp-27` also name the OTP release they were built for.
+        let elixir = version_managers::tool_version(worktree, "elixir");
+        let (elixir, elixir_otp) = match elixir.as_deref().and_then(|v| v.split_once("-otp-")) {
+            Some((elixir, otp)) => (Some(elixir.to_string()), otp_release(otp)),
+            None => (elixir, None),
+        };
+
+        let otp = version_managers::tool_version(worktree, "erlang")
+            .and_then(|erlang| otp_release(&erlang))
+            .or(elixir_otp);
+
+        Self {
+            elixir: elixir.and_then(|elixir| Version::parse(&elixir)),
+            otp,
+            newest_allowed_elixir: None,
+        }
+    }
+
+    /// Parses the output of `elixir --version`, which looks like:
+    ///
+    /// ```text
+    /// Erlang/OTP 27 [erts-15.1] [source] [64-bit] [smp:10:10] [ds:10:10:10] [async-threads:1]
+    ///
+    /// Elixir 1.17.3 (compiled with Erlang/OTP 27)
+    /// ```
+    fn from_elixir_executable(worktree: &Worktree) -> Self {
+        // The executable is resolved from the worktree's `PATH`, as the `process:exec` capability
+        // only allows running `elixir` by name.
+        if worktree.which("elixir").is_none() {
+            return Self::default();
+        }
+
+        let Ok(output) = Command::new("elixir")
+            .arg("--version")
+            .envs(worktree.shell_env())
+            .output()
+        else {
+            return Self::default();
+        };
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let word_after = |prefix: &str| {
+            output
+                .lines()
+                .find_map(|line| line.strip_prefix(prefix))
+                .and_then(|rest| rest.split_whitespace().next())
+        };
+
+        Self {
+            elixir: word_after("Elixir ").and_then(Version::parse),
+            otp: word_after("Erlang/OTP ").and_then(otp_release),
+            newest_allowed_elixir: None,
+        }
+    }
+}
+
+fn otp_release(version: &str) -> Option<u64> {
+    version
+        .trim_start_matches("OTP-")
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Reads the newest Elixir version allowed by the `elixir:` requirement in `mix.exs`.
+///
+/// `~> 1.12.0` and `== 1.12.3` allow nothing newer than Elixir 1.12, while `~> 1.12` and
+/// `>= 1.12.0` don't cap the version at all.
+fn mix_newest_allowed_elixir(mix_exs: &str) -> Option<Version> {
+    let requirement = mix_exs
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("elixir:"))?
+        .split('"')
+        .nth(1)?;
+
+    // Any alternative may allow newer versions, while every condition must hold.
+    if requirement.contains(" or ") {
+        return None;
+    }
+    requirement.split(" and ").filter_map(newest_allowed).min()
+}
+
+fn newest_allowed(condition: &str) -> Option<Version> {
+    let condition = condition.trim();
+    let (operator, version) = condition.split_at(condition.find(|c: char| c.is_ascii_digit())?);
+    let version = Version::parse(version)?;
+
+    match operator.trim() {
+        "~>" => version.without_last_number(),
+        "==" | "<=" | "" => Some(version),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn newest_allowed(requirement: &str) -> Option<String> {
+        let mix_exs = format!("  def project do\n    [\n      elixir: \"{requirement}\",\n");
+        mix_newest_allowed_elixir(&mix_exs).map(|version| version.to_string())
+    }
+
+    #[test]
+    fn mix_requirements_without_an_upper_bound_allow_any_version() {
+        assert_eq!(newest_allowed("~> 1.12"), None);
+        assert_eq!(newest_allowed(">= 1.12.0"), None);
+        assert_eq!(newest_allowed("~> 1.12.0 or ~> 1.15"), None);
+    }
+
+    #[test]
+    fn mix_requirements_cap_the_newest_version() {
+        assert_eq!(newest_allowed("~> 1.12.0").as_deref(), Some("1.12"));
+        assert_eq!(newest_allowed("== 1.13.4").as_deref(), Some("1.13.4"));
+        assert_eq!(
+            newest_allowed(">= 1.11.0 and <= 1.13.0").as_deref(),
+            Some("1.13.0")
+        );
+    }
+}
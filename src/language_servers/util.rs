@@ -1,31 +1,75 @@
-use std::fs;
-
-use zed_extension_api::Result;
-
-pub(super) fn remove_outdated_versions(language_server_id: &str, version_dir: &str) -> Result<()> {
-    let entries = fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("failed to load directory entry {e}"))?;
-        if entry.file_name().to_str().is_none_or(|file_name| {
-            file_name.starts_with(language_server_id) && file_name != version_dir
-        }) {
-            fs::remove_dir_all(entry.path()).ok();
-        }
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Lists the names of the directories in the extension's working directory.
+pub(super) fn list_dirs() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(".") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+pub(super) fn remove_dirs(dirs: impl IntoIterator<Item = String>) {
+    for dir in dirs {
+        fs::remove_dir_all(dir).ok();
     }
-    Ok(())
 }
 
-pub(super) fn find_existing_binary(language_server_id: &str, binary_name: &str) -> Option<String> {
-    fs::read_dir(".").ok()?.flatten().find_map(|entry| {
-        let binary_path = entry.path().join(binary_name);
-
-        if binary_path.is_file()
-            && let Some(binary_dir) = entry.file_name().to_str()
-            && binary_dir.starts_with(language_server_id)
-        {
-            Some(binary_path.to_string_lossy().to_string())
-        } else {
-            None
+/// Returns the current UTC date formatted as `YYYYMMDD`.
+pub(super) fn current_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+
+    format_date(days)
+}
+
+/// Formats a number of days since the Unix epoch as a proleptic Gregorian `YYYYMMDD` date.
+fn format_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}{month:02}{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        for (days, date) in [
+            (0, "19700101"),
+            (10_956, "19991231"),
+            (10_957, "20000101"),
+            (11_016, "20000229"),
+            (11_017, "20000301"),
+            (19_722, "20231231"),
+            (19_723, "20240101"),
+            (19_782, "20240229"),
+            (47_541, "21000301"),
+        ] {
+            assert_eq!(format_date(days), date, "{days} days");
         }
-    })
+    }
 }
@@ -0,0 +1,166 @@
+use std::{cmp::Ordering, fmt};
+
+/// A release version such as `v0.27.2`, `1.0.0-rc.1` or a nightly `20250101` date.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Version {
+    numbers: Vec<u64>,
+    pre_release: Option<String>,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let (numbers, pre_release) = match version.split_once('-') {
+            Some((numbers, pre_release)) => (numbers, Some(pre_release.to_string())),
+            None => (version, None),
+        };
+
+        let numbers = numbers
+            .split('.')
+            .map(|number| {
+                if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                number.parse().ok()
+            })
+            .collect::<Option<Vec<u64>>>()?;
+
+        if pre_release.as_ref().is_some_and(|pre| pre.is_empty()) {
+            return None;
+        }
+
+        Some(Self {
+            numbers,
+            pre_release,
+        })
+    }
+
+    /// Drops the last number, turning `1.12.0` into `1.12`, as `~> 1.12.0` allows any `1.12.x`.
+    /// Returns `None` when less than two numbers would remain.
+    pub fn without_last_number(&self) -> Option<Self> {
+        let numbers = &self.numbers[..self.numbers.len().checked_sub(1)?];
+        (numbers.len() >= 2).then(|| Self {
+            numbers: numbers.to_vec(),
+            pre_release: None,
+        })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers.cmp(&other.numbers).then_with(|| {
+            match (&self.pre_release, &other.pre_release) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other_pre)) => compare_pre_releases(pre, other_pre),
+            }
+        })
+    }
+}
+
+/// Compares pre-releases such as `rc.2` and `rc.10` the way semver does: identifier by identifier,
+/// numerically when both are numbers, with numbers sorting before other identifiers and a shorter
+/// pre-release sorting before a longer one it is a prefix of.
+fn compare_pre_releases(pre: &str, other_pre: &str) -> Ordering {
+    let mut identifiers = pre.split('.');
+    let mut other_identifiers = other_pre.split('.');
+
+    loop {
+        let ordering = match (identifiers.next(), other_identifiers.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(identifier), Some(other_identifier)) => {
+                match (identifier.parse::<u64>(), other_identifier.parse::<u64>()) {
+                    (Ok(number), Ok(other_number)) => number.cmp(&other_number),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => identifier.cmp(other_identifier),
+                }
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let numbers = self
+            .numbers
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        write!(f, "{numbers}")?;
+
+        if let Some(pre_release) = &self.pre_release {
+            write!(f, "-{pre_release}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(version("v0.27.2").to_string(), "0.27.2");
+        assert_eq!(version("1.0.0-rc.1").to_string(), "1.0.0-rc.1");
+        assert_eq!(version("20250101").to_string(), "20250101");
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for invalid in ["", "v", "nightly", "1..2", "1.2.", "1.x", "+1.2", "1.2-"] {
+            assert_eq!(Version::parse(invalid), None, "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn orders_release_numbers_numerically() {
+        assert!(version("0.10.0") > version("0.9.0"));
+        assert!(version("1.0") < version("1.0.1"));
+        assert!(version("v1.2.3") == version("1.2.3"));
+    }
+
+    #[test]
+    fn orders_pre_releases_before_releases() {
+        assert!(version("1.0.0-rc.1") < version("1.0.0"));
+        assert!(version("1.0.0-rc.1") > version("0.9.0"));
+    }
+
+    #[test]
+    fn orders_pre_releases_like_semver() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0-rc.2",
+            "1.0.0-rc.10",
+            "1.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(version(pair[0]) < version(pair[1]), "{pair:?}");
+        }
+    }
+}
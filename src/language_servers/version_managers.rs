@@ -0,0 +1,183 @@
+use zed_extension_api::{Worktree, process::Command};
+
+const MISE_CONFIG_FILES: &[&str] = &[".mise.toml", "mise.toml", ".config/mise.toml"];
+
+/// Returns the version of a tool the worktree declares in `.tool-versions` or a mise config file.
+pub(super) fn tool_version(worktree: &Worktree, tool: &str) -> Option<String> {
+    asdf_tool_version(worktree, tool).or_else(|| mise_tool_version(worktree, tool))
+}
+
+/// Resolves a language server the worktree declares as an asdf or mise tool with `asdf which` or
+/// `mise which`.
+///
+/// The extension runs sandboxed and can't look into the version managers' shim directories, so it
+/// asks the version manager instead. This also ensures the declared version is actually installed,
+/// otherwise the server is downloaded instead.
+pub(super) fn which(worktree: &Worktree, tool: &str, binary_name: &str) -> Option<String> {
+    let env = worktree.shell_env();
+
+    let version_manager = if mise_tool_version(worktree, tool).is_some() {
+        "mise"
+    } else if asdf_tool_version(worktree, tool).is_some() {
+        // mise also reads `.tool-versions`, so only prefer it when asdf is not configured.
+        let uses_mise = env.iter().any(|(key, _)| key.starts_with("MISE_"))
+            && !env.iter().any(|(key, _)| key.starts_with("ASDF_"));
+        if uses_mise { "mise" } else { "asdf" }
+    } else {
+        return None;
+    };
+
+    let output = Command::new(version_manager)
+        .arg("which")
+        .arg(binary_name)
+        .envs(env)
+        .output()
+        .ok()?;
+    if output.status != Some(0) {
+        return None;
+    }
+
+    let path = String::from_utf8(output.stdout).ok()?;
+    let path = path.trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+fn asdf_tool_version(worktree: &Worktree, tool: &str) -> Option<String> {
+    let tool_versions = worktree.read_text_file(".tool-versions").ok()?;
+
+    tool_versions.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut parts = line.split_whitespace();
+        let name = parts.next()?;
+        is_tool(name, tool).then(|| parts.next().map(str::to_string))?
+    })
+}
+
+fn mise_tool_version(worktree: &Worktree, tool: &str) -> Option<String> {
+    MISE_CONFIG_FILES.iter().find_map(|path| {
+        let config = worktree.read_text_file(path).ok()?;
+        parse_mise_tools(&config)
+            .into_iter()
+            .find_map(|(name, version)| is_tool(&name, tool).then_some(version))
+    })
+}
+
+/// Reads the `[tools]` table of a mise config, accepting `tool = "1.0"`, `tool = ["1.0"]` and
+/// `tool = { version = "1.0" }` entries.
+fn parse_mise_tools(config: &str) -> Vec<(String, String)> {
+    let mut in_tools = false;
+    let mut tools = Vec::new();
+
+    for line in config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_tools = line == "[tools]";
+            continue;
+        }
+        if !in_tools {
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let name = name.trim().trim_matches(['"', '\'']);
+        if name.is_empty() || name.starts_with('#') {
+            continue;
+        }
+        if let Some(version) = first_string(value).filter(|version| !version.is_empty()) {
+            tools.push((name.to_string(), version.to_string()));
+        }
+    }
+
+    tools
+}
+
+/// Returns the first quoted string of a TOML value, ignoring anything after a `#` comment.
+fn first_string(value: &str) -> Option<&str> {
+    let start = value.find(['"', '\''])?;
+    if value[..start].contains('#') {
+        return None;
+    }
+
+    let quote = value[start..].chars().next()?;
+    let rest = &value[start + 1..];
+    Some(&rest[..rest.find(quote)?])
+}
+
+/// Matches tool names regardless of backend prefixes (`asdf:elixir-ls`, `ubi:owner/next-ls`) and
+/// of dashes, so `nextls` and `next-ls` are treated as the same tool.
+fn is_tool(name: &str, tool: &str) -> bool {
+    let name = name.rsplit([':', '/']).next().unwrap_or(name);
+    let normalize = |name: &str| name.replace(['-', '_'], "");
+    normalize(name) == normalize(tool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_tools(config: &str, expected: &[(&str, &str)]) {
+        let expected = expected
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(parse_mise_tools(config), expected);
+    }
+
+    #[test]
+    fn parses_mise_tools() {
+        let config = r#"
+[env]
+elixir = "ignored"
+
+[tools]
+elixir = "1.17.3-otp-27"
+erlang = ["27.1", "26.2"]
+"asdf:elixir-ls" = { version = "0.27.2" }
+'ubi:elixir-tools/next-ls' = 'latest'
+"#;
+        assert_tools(
+            config,
+            &[
+                ("elixir", "1.17.3-otp-27"),
+                ("erlang", "27.1"),
+                ("asdf:elixir-ls", "0.27.2"),
+                ("ubi:elixir-tools/next-ls", "latest"),
+            ],
+        );
+    }
+
+    #[test]
+    fn ignores_mise_comments() {
+        let config = r#"
+[tools]
+# lexical = "0.7.0"
+elixir = "1.17" # "1.16" is too old
+erlang = [] # "27"
+"#;
+        assert_tools(config, &[("elixir", "1.17")]);
+    }
+
+    #[test]
+    fn skips_mise_tools_without_a_version() {
+        let config = r#"
+[tools]
+elixir =
+erlang = ""
+next-ls = {}
+"#;
+        assert_tools(config, &[]);
+    }
+
+    #[test]
+    fn matches_tools_across_backends_and_spellings() {
+        assert!(is_tool("elixir-ls", "elixir-ls"));
+        assert!(is_tool("asdf:elixir-ls", "elixir-ls"));
+        assert!(is_tool("ubi:elixir-tools/next-ls", "next-ls"));
+        assert!(is_tool("nextls", "next-ls"));
+        assert!(is_tool("next_ls", "next-ls"));
+        assert!(!is_tool("elixir", "elixir-ls"));
+        assert!(!is_tool("asdf:elixir-ls", "elixir"));
+    }
+}